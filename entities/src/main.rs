@@ -33,10 +33,10 @@ async fn generate_seaorm_entities(database_uri: &str, output_dir: &str) -> io::R
         println!("\n✅ Entity generation successful!");
         Ok(())
     } else {
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("Command failed with exit code: {:?}", status.code()),
-        ))
+        Err(io::Error::other(format!(
+            "Command failed with exit code: {:?}",
+            status.code()
+        )))
     }
 }
 
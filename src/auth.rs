@@ -13,6 +13,8 @@ pub struct Permission {
     pub scope: String,
 }
 
+// Permission checks are kept for the mutations that will need them
+#[allow(dead_code)]
 impl Permission {
     pub fn new(action: &str, resource: &str, scope: &str) -> Self {
         Self {
@@ -31,10 +33,12 @@ impl Permission {
             None
         }
     }
+}
 
-    /// Convert to "action:resource:scope" format
-    pub fn to_string(&self) -> String {
-        format!("{}:{}:{}", self.action, self.resource, self.scope)
+/// Formats as "action:resource:scope"
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.action, self.resource, self.scope)
     }
 }
 
@@ -47,6 +51,7 @@ pub struct Claims {
     pub permissions: Vec<Permission>,
 }
 
+#[allow(dead_code)]
 impl Claims {
     /// Check if the user has a specific permission
     pub fn has_permission(&self, action: &str, resource: &str) -> bool {
@@ -90,6 +95,7 @@ pub fn verify_token(token: String) -> Option<Claims> {
     Some(token.claims)
 }
 
+#[allow(dead_code)]
 pub fn assert_logged_in(user: &Option<Claims>) -> juniper::FieldResult<&Claims> {
     user.as_ref().ok_or_else(|| juniper::FieldError::new(
         "Authentication required",
@@ -0,0 +1,336 @@
+use std::{collections::HashMap, sync::Arc};
+
+use entities::sea_orm_active_enums::DataTypes;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+// Value rows of one entry, from every value table
+#[derive(Default)]
+pub struct EntryValues {
+    pub text: Vec<entities::entry_text_values::Model>,
+    pub typst_text: Vec<entities::entry_typst_text_values::Model>,
+    pub boolean: Vec<entities::entry_boolean_values::Model>,
+    pub number: Vec<entities::entry_number_values::Model>,
+    pub date_time: Vec<entities::entry_date_time_values::Model>,
+    pub text_list: Vec<entities::entry_text_list_values::Model>,
+    pub number_list: Vec<entities::entry_number_list_values::Model>,
+    pub relation: Vec<entities::entry_relation_values::Model>,
+    pub object: Vec<entities::entry_object_values::Model>,
+}
+
+/// What the selection set of a list of entries needs besides the entry rows.
+#[derive(Default, Clone, Copy)]
+pub struct EntryPrefetch {
+    pub values: bool,          // Entry.values is selected
+    pub related_entries: bool, // RelationListValue.entries is selected below it
+}
+
+#[derive(Default)]
+struct ValueCache {
+    pending: Vec<Uuid>,
+    loaded: HashMap<Uuid, Arc<EntryValues>>,
+}
+
+/// Request-scoped loaders.
+///
+/// Field lists are cached per collection. Entry values are cached per entry and
+/// loaded for every entry queued with [`Loaders::prime`] at once, with one query
+/// per value table, so resolving the values of a page of entries costs the same
+/// as resolving those of a single one. Entries are cached by id for relation
+/// targets, and [`Loaders::prefetch`] fills both caches from the look-ahead.
+#[derive(Default)]
+pub struct Loaders {
+    fields: Mutex<HashMap<Uuid, Arc<Vec<entities::fields::Model>>>>,
+    values: Mutex<ValueCache>,
+    entries: Mutex<HashMap<Uuid, Option<entities::entries::Model>>>,
+}
+
+impl Loaders {
+    pub async fn fields(&self, db: &DatabaseConnection, collection_id: Uuid) -> Result<Arc<Vec<entities::fields::Model>>, DbErr> {
+        let mut cache = self.fields.lock().await;
+        if let Some(fields) = cache.get(&collection_id) {
+            return Ok(fields.clone());
+        }
+
+        let fields = Arc::new(
+            entities::fields::Entity::find()
+                .filter(entities::fields::Column::CollectionId.eq(collection_id))
+                .all(db)
+                .await?,
+        );
+        cache.insert(collection_id, fields.clone());
+        Ok(fields)
+    }
+
+    /// Queue entries whose values are likely to be requested, such as a page of results.
+    pub async fn prime(&self, mut entry_ids: Vec<Uuid>) {
+        let mut cache = self.values.lock().await;
+        entry_ids.retain(|id| !cache.loaded.contains_key(id));
+        cache.pending.extend(entry_ids);
+    }
+
+    /// Values of an entry, loading them together with every queued entry.
+    pub async fn values(&self, db: &DatabaseConnection, entry_id: Uuid) -> Result<Arc<EntryValues>, DbErr> {
+        let mut cache = self.values.lock().await;
+        if let Some(values) = cache.loaded.get(&entry_id) {
+            return Ok(values.clone());
+        }
+
+        let mut ids = std::mem::take(&mut cache.pending);
+        ids.push(entry_id);
+        Self::load_values(db, &mut cache, ids).await?;
+        Ok(cache.loaded[&entry_id].clone())
+    }
+
+    /// Values of some fields of an entry. Entries whose values are loaded or
+    /// queued share the batch; others only query the value tables of these fields.
+    pub async fn field_values(
+        &self,
+        db: &DatabaseConnection,
+        entry_id: Uuid,
+        fields: &[&entities::fields::Model],
+    ) -> Result<Arc<EntryValues>, DbErr> {
+        let batched = {
+            let cache = self.values.lock().await;
+            cache.loaded.contains_key(&entry_id) || cache.pending.contains(&entry_id)
+        };
+        if batched {
+            return self.values(db, entry_id).await;
+        }
+
+        let mut values = EntryValues::default();
+        let mut data_types: Vec<&DataTypes> = Vec::new();
+        for field in fields {
+            if !data_types.contains(&&field.data_type) {
+                data_types.push(&field.data_type);
+            }
+        }
+        for data_type in data_types {
+            let field_ids: Vec<Uuid> = fields.iter().filter(|f| &f.data_type == data_type).map(|f| f.id).collect();
+            match data_type {
+                DataTypes::Text => {
+                    values.text.extend(
+                        entities::entry_text_values::Entity::find()
+                            .filter(entities::entry_text_values::Column::EntryId.eq(entry_id))
+                            .filter(entities::entry_text_values::Column::FieldId.is_in(field_ids))
+                            .all(db)
+                            .await?,
+                    );
+                }
+                DataTypes::TypstText => {
+                    values.typst_text.extend(
+                        entities::entry_typst_text_values::Entity::find()
+                            .filter(entities::entry_typst_text_values::Column::EntryId.eq(entry_id))
+                            .filter(entities::entry_typst_text_values::Column::FieldId.is_in(field_ids))
+                            .all(db)
+                            .await?,
+                    );
+                }
+                DataTypes::Boolean => {
+                    values.boolean.extend(
+                        entities::entry_boolean_values::Entity::find()
+                            .filter(entities::entry_boolean_values::Column::EntryId.eq(entry_id))
+                            .filter(entities::entry_boolean_values::Column::FieldId.is_in(field_ids))
+                            .all(db)
+                            .await?,
+                    );
+                }
+                DataTypes::Number => {
+                    values.number.extend(
+                        entities::entry_number_values::Entity::find()
+                            .filter(entities::entry_number_values::Column::EntryId.eq(entry_id))
+                            .filter(entities::entry_number_values::Column::FieldId.is_in(field_ids))
+                            .all(db)
+                            .await?,
+                    );
+                }
+                DataTypes::DateTime => {
+                    values.date_time.extend(
+                        entities::entry_date_time_values::Entity::find()
+                            .filter(entities::entry_date_time_values::Column::EntryId.eq(entry_id))
+                            .filter(entities::entry_date_time_values::Column::FieldId.is_in(field_ids))
+                            .all(db)
+                            .await?,
+                    );
+                }
+                DataTypes::TextList => {
+                    values.text_list.extend(
+                        entities::entry_text_list_values::Entity::find()
+                            .filter(entities::entry_text_list_values::Column::EntryId.eq(entry_id))
+                            .filter(entities::entry_text_list_values::Column::FieldId.is_in(field_ids))
+                            .all(db)
+                            .await?,
+                    );
+                }
+                DataTypes::NumberList => {
+                    values.number_list.extend(
+                        entities::entry_number_list_values::Entity::find()
+                            .filter(entities::entry_number_list_values::Column::EntryId.eq(entry_id))
+                            .filter(entities::entry_number_list_values::Column::FieldId.is_in(field_ids))
+                            .all(db)
+                            .await?,
+                    );
+                }
+                DataTypes::Relation => {
+                    values.relation.extend(
+                        entities::entry_relation_values::Entity::find()
+                            .filter(entities::entry_relation_values::Column::FromEntryId.eq(entry_id))
+                            .filter(entities::entry_relation_values::Column::FieldId.is_in(field_ids))
+                            .all(db)
+                            .await?,
+                    );
+                }
+                DataTypes::Object => {
+                    values.object.extend(
+                        entities::entry_object_values::Entity::find()
+                            .filter(entities::entry_object_values::Column::EntryId.eq(entry_id))
+                            .filter(entities::entry_object_values::Column::FieldId.is_in(field_ids))
+                            .all(db)
+                            .await?,
+                    );
+                }
+            }
+        }
+        Ok(Arc::new(values))
+    }
+
+    /// Load up front what the selection set will ask for about these entries:
+    /// at most one query per value table and one for the related entries.
+    pub async fn prefetch(&self, db: &DatabaseConnection, entry_ids: Vec<Uuid>, prefetch: EntryPrefetch) -> Result<(), DbErr> {
+        if !prefetch.values {
+            // Whatever the look-ahead missed still loads in one batch on first use
+            self.prime(entry_ids).await;
+            return Ok(());
+        }
+
+        let mut cache = self.values.lock().await;
+        let mut ids = std::mem::take(&mut cache.pending);
+        ids.extend(entry_ids.iter().filter(|id| !cache.loaded.contains_key(id)));
+        Self::load_values(db, &mut cache, ids).await?;
+
+        if prefetch.related_entries {
+            let related: Vec<Uuid> = entry_ids
+                .iter()
+                .filter_map(|id| cache.loaded.get(id))
+                .flat_map(|values| values.relation.iter().map(|r| r.to_entry_id))
+                .collect();
+            drop(cache);
+            self.load_entries(db, related.clone()).await?;
+            // Their own values batch together if they are asked for as well
+            self.prime(related).await;
+        }
+
+        Ok(())
+    }
+
+    /// Entries by id, from the entries loaded so far when possible. Ids of
+    /// missing entries are skipped.
+    pub async fn entries(&self, db: &DatabaseConnection, ids: &[Uuid]) -> Result<Vec<entities::entries::Model>, DbErr> {
+        self.load_entries(db, ids.to_vec()).await?;
+        let cache = self.entries.lock().await;
+        Ok(ids.iter().filter_map(|id| cache.get(id).cloned().flatten()).collect())
+    }
+
+    async fn load_entries(&self, db: &DatabaseConnection, mut ids: Vec<Uuid>) -> Result<(), DbErr> {
+        let mut cache = self.entries.lock().await;
+        ids.retain(|id| !cache.contains_key(id));
+        ids.sort();
+        ids.dedup();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut found: HashMap<Uuid, entities::entries::Model> = entities::entries::Entity::find()
+            .filter(entities::entries::Column::Id.is_in(ids.clone()))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|e| (e.id, e))
+            .collect();
+        for id in ids {
+            cache.insert(id, found.remove(&id));
+        }
+        Ok(())
+    }
+
+    async fn load_values(db: &DatabaseConnection, cache: &mut ValueCache, mut ids: Vec<Uuid>) -> Result<(), DbErr> {
+        ids.sort();
+        ids.dedup();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut batch: HashMap<Uuid, EntryValues> = HashMap::new();
+        for row in entities::entry_text_values::Entity::find()
+            .filter(entities::entry_text_values::Column::EntryId.is_in(ids.clone()))
+            .all(db)
+            .await?
+        {
+            batch.entry(row.entry_id).or_default().text.push(row);
+        }
+        for row in entities::entry_typst_text_values::Entity::find()
+            .filter(entities::entry_typst_text_values::Column::EntryId.is_in(ids.clone()))
+            .all(db)
+            .await?
+        {
+            batch.entry(row.entry_id).or_default().typst_text.push(row);
+        }
+        for row in entities::entry_boolean_values::Entity::find()
+            .filter(entities::entry_boolean_values::Column::EntryId.is_in(ids.clone()))
+            .all(db)
+            .await?
+        {
+            batch.entry(row.entry_id).or_default().boolean.push(row);
+        }
+        for row in entities::entry_number_values::Entity::find()
+            .filter(entities::entry_number_values::Column::EntryId.is_in(ids.clone()))
+            .all(db)
+            .await?
+        {
+            batch.entry(row.entry_id).or_default().number.push(row);
+        }
+        for row in entities::entry_date_time_values::Entity::find()
+            .filter(entities::entry_date_time_values::Column::EntryId.is_in(ids.clone()))
+            .all(db)
+            .await?
+        {
+            batch.entry(row.entry_id).or_default().date_time.push(row);
+        }
+        for row in entities::entry_text_list_values::Entity::find()
+            .filter(entities::entry_text_list_values::Column::EntryId.is_in(ids.clone()))
+            .all(db)
+            .await?
+        {
+            batch.entry(row.entry_id).or_default().text_list.push(row);
+        }
+        for row in entities::entry_number_list_values::Entity::find()
+            .filter(entities::entry_number_list_values::Column::EntryId.is_in(ids.clone()))
+            .all(db)
+            .await?
+        {
+            batch.entry(row.entry_id).or_default().number_list.push(row);
+        }
+        for row in entities::entry_relation_values::Entity::find()
+            .filter(entities::entry_relation_values::Column::FromEntryId.is_in(ids.clone()))
+            .all(db)
+            .await?
+        {
+            batch.entry(row.from_entry_id).or_default().relation.push(row);
+        }
+        for row in entities::entry_object_values::Entity::find()
+            .filter(entities::entry_object_values::Column::EntryId.is_in(ids.clone()))
+            .all(db)
+            .await?
+        {
+            batch.entry(row.entry_id).or_default().object.push(row);
+        }
+
+        // Entries without any value row are cached too
+        for id in ids {
+            let values = batch.remove(&id).unwrap_or_default();
+            cache.loaded.insert(id, Arc::new(values));
+        }
+        Ok(())
+    }
+}
@@ -16,6 +16,7 @@ use crate::{setup::SetupResult, state::AppData};
 
 mod auth;
 mod config;
+mod loaders;
 mod schema;
 mod setup;
 mod state;
@@ -3,6 +3,7 @@ use crate::state::AppData;
 
 mod query;
 mod objects;
+mod relative_time;
 
 pub type Schema<'a> = RootNode<'a, query::Query, juniper::EmptyMutation<AppData>, juniper::EmptySubscription<AppData>>;
 
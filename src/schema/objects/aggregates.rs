@@ -0,0 +1,576 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use entities::sea_orm_active_enums::DataTypes;
+use juniper::{FieldResult, GraphQLEnum, GraphQLObject, Value};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait, FromQueryResult, PaginatorTrait,
+    QueryFilter, QuerySelect,
+    sea_query::{
+        Alias, Asterisk, Expr, Func, Iden, IntoTableRef, JoinType, NullOrdering, Order, Query, SelectStatement,
+        SimpleExpr, TableRef,
+    },
+};
+use uuid::Uuid;
+
+use super::collection::Field;
+use super::entries::Entry;
+use super::filters::{entry_ids, validate_field};
+use super::ordering::SortKey;
+
+#[derive(GraphQLObject)]
+pub struct NumberAggregate {
+    pub field_name: String,
+    pub count: i32, // Entries with a value for the field
+    pub sum: Option<f64>,
+    pub avg: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+#[derive(GraphQLObject)]
+pub struct DateTimeAggregate {
+    pub field_name: String,
+    pub count: i32, // Entries with a value for the field
+    pub min: Option<DateTime<Utc>>,
+    pub max: Option<DateTime<Utc>>,
+}
+
+#[derive(GraphQLObject)]
+pub struct EntryAggregate {
+    pub count: i32, // Entries matching the filters
+    pub numbers: Vec<NumberAggregate>,
+    pub date_times: Vec<DateTimeAggregate>,
+}
+
+// Aggregate the Number and DateTime fields named in `field_names`, or all of
+// them, over the entries matching `condition`
+pub async fn aggregate_entries(
+    db: &DatabaseConnection,
+    fields: &[Field],
+    condition: Condition,
+    field_names: Option<Vec<String>>,
+) -> FieldResult<EntryAggregate> {
+    let targets: Vec<&Field> = match field_names {
+        Some(names) => names
+            .iter()
+            .map(|name| validate_field(fields, name, &[DataTypes::Number, DataTypes::DateTime]))
+            .collect::<FieldResult<_>>()?,
+        None => fields
+            .iter()
+            .filter(|f| matches!(f.data_type, DataTypes::Number | DataTypes::DateTime))
+            .collect(),
+    };
+
+    let count = entities::entries::Entity::find()
+        .filter(condition.clone())
+        .count(db)
+        .await?;
+
+    let mut aggregate = EntryAggregate {
+        count: count as i32,
+        numbers: Vec::new(),
+        date_times: Vec::new(),
+    };
+
+    for field in targets {
+        if field.data_type == DataTypes::Number {
+            let column = || Expr::col(entities::entry_number_values::Column::Value);
+            let row = entities::entry_number_values::Entity::find()
+                .select_only()
+                .column_as(column().count(), "count")
+                .column_as(column().sum(), "sum")
+                .column_as(SimpleExpr::from(Func::avg(column())), "avg")
+                .column_as(column().min(), "min")
+                .column_as(column().max(), "max")
+                .filter(entities::entry_number_values::Column::FieldId.eq(field.id))
+                .filter(entities::entry_number_values::Column::EntryId.in_subquery(entry_ids(condition.clone())))
+                .into_model::<NumberAggregateRow>()
+                .one(db)
+                .await?
+                .unwrap_or_default();
+            aggregate.numbers.push(NumberAggregate {
+                field_name: field.name.clone(),
+                count: row.count as i32,
+                sum: row.sum,
+                avg: row.avg,
+                min: row.min,
+                max: row.max,
+            });
+        } else {
+            let column = || Expr::col(entities::entry_date_time_values::Column::Value);
+            let row: Option<(i64, Option<NaiveDateTime>, Option<NaiveDateTime>)> =
+                entities::entry_date_time_values::Entity::find()
+                    .select_only()
+                    .column_as(column().count(), "count")
+                    .column_as(column().min(), "min")
+                    .column_as(column().max(), "max")
+                    .filter(entities::entry_date_time_values::Column::FieldId.eq(field.id))
+                    .filter(entities::entry_date_time_values::Column::EntryId.in_subquery(entry_ids(condition.clone())))
+                    .into_tuple()
+                    .one(db)
+                    .await?;
+            let (count, min, max) = row.unwrap_or_default();
+            aggregate.date_times.push(DateTimeAggregate {
+                field_name: field.name.clone(),
+                count: count as i32,
+                min: min.map(|d| d.and_utc()),
+                max: max.map(|d| d.and_utc()),
+            });
+        }
+    }
+
+    Ok(aggregate)
+}
+
+#[derive(GraphQLObject)]
+pub struct FacetValue {
+    pub value: String, // Text, "true"/"false", list element or related entry id
+    pub label: Option<String>, // Name of the related entry for Relation fields
+    pub count: i32, // Matching entries holding the value
+}
+
+#[derive(GraphQLObject)]
+pub struct Facet {
+    pub field_name: String,
+    pub values: Vec<FacetValue>,
+}
+
+#[derive(FromQueryResult, Default)]
+struct NumberAggregateRow {
+    count: i64,
+    sum: Option<f64>,
+    avg: Option<f64>,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+#[derive(FromQueryResult)]
+struct FacetRow {
+    facet_value: Option<String>,
+    count: i64,
+}
+
+// Distinct values of each named field with the number of matching entries
+// holding them, most common first
+pub async fn facet_entries(
+    db: &DatabaseConnection,
+    fields: &[Field],
+    condition: Condition,
+    field_names: Vec<String>,
+) -> FieldResult<Vec<Facet>> {
+    let backend = db.get_database_backend();
+
+    let mut facets = Vec::with_capacity(field_names.len());
+    for name in field_names {
+        let field = validate_field(fields, &name, &[
+            DataTypes::Text,
+            DataTypes::Boolean,
+            DataTypes::TextList,
+            DataTypes::Relation,
+        ])?;
+
+        let statement = match field.data_type {
+            DataTypes::Text => facet_statement(
+                entities::entry_text_values::Entity,
+                entities::entry_text_values::Column::EntryId,
+                entities::entry_text_values::Column::FieldId,
+                entities::entry_text_values::Column::Value,
+                false,
+                field.id,
+                condition.clone(),
+            ),
+            DataTypes::Boolean => facet_statement(
+                entities::entry_boolean_values::Entity,
+                entities::entry_boolean_values::Column::EntryId,
+                entities::entry_boolean_values::Column::FieldId,
+                entities::entry_boolean_values::Column::Value,
+                false,
+                field.id,
+                condition.clone(),
+            ),
+            // Every list element is its own value
+            DataTypes::TextList => facet_statement(
+                entities::entry_text_list_values::Entity,
+                entities::entry_text_list_values::Column::EntryId,
+                entities::entry_text_list_values::Column::FieldId,
+                entities::entry_text_list_values::Column::Value,
+                true,
+                field.id,
+                condition.clone(),
+            ),
+            DataTypes::Relation => facet_statement(
+                entities::entry_relation_values::Entity,
+                entities::entry_relation_values::Column::FromEntryId,
+                entities::entry_relation_values::Column::FieldId,
+                entities::entry_relation_values::Column::ToEntryId,
+                false,
+                field.id,
+                condition.clone(),
+            ),
+            _ => unreachable!(), // validate_field ensures correct types
+        };
+
+        let rows = FacetRow::find_by_statement(backend.build(&statement)).all(db).await?;
+        let mut values: Vec<FacetValue> = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(FacetValue {
+                    value: row.facet_value?,
+                    label: None,
+                    count: row.count as i32,
+                })
+            })
+            .collect();
+
+        // Label relation targets with their entry names
+        if field.data_type == DataTypes::Relation {
+            let names = entry_names(db, values.iter().map(|v| v.value.as_str())).await?;
+            for value in &mut values {
+                value.label = names.get(&value.value).cloned();
+            }
+        }
+
+        facets.push(Facet {
+            field_name: field.name.clone(),
+            values,
+        });
+    }
+
+    Ok(facets)
+}
+
+// Names of the entries with the given ids, keyed by id as text
+async fn entry_names<'a>(
+    db: &DatabaseConnection,
+    ids: impl Iterator<Item = &'a str>,
+) -> FieldResult<HashMap<String, String>> {
+    let ids: Vec<Uuid> = ids.filter_map(|id| Uuid::parse_str(id).ok()).collect();
+    let names = entities::entries::Entity::find()
+        .filter(entities::entries::Column::Id.is_in(ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|e| (e.id.to_string(), e.name))
+        .collect();
+    Ok(names)
+}
+
+// Count matching entries per value of `field_id` in a value table, unnesting
+// array values when `unnest` is set
+fn facet_statement<T, C>(
+    table: T,
+    entry_column: C,
+    field_column: C,
+    value_column: C,
+    unnest: bool,
+    field_id: Uuid,
+    condition: Condition,
+) -> SelectStatement
+where
+    T: Iden + Copy + 'static,
+    C: Iden + Copy + 'static,
+{
+    let element = Alias::new("element");
+    let mut statement = Query::select();
+    statement.from(table);
+
+    let value: SimpleExpr = if unnest {
+        statement.from_function(
+            Func::cust(Alias::new("unnest")).arg(Expr::col((table, value_column))),
+            element.clone(),
+        );
+        Expr::col(element).into()
+    } else {
+        Expr::col((table, value_column)).into()
+    };
+
+    statement
+        .expr_as(Expr::cust_with_exprs("CAST($1 AS text)", [value.clone()]), Alias::new("facet_value"))
+        .expr_as(Func::count_distinct(Expr::col((table, entry_column))), Alias::new("count"))
+        .and_where(Expr::col((table, field_column)).eq(field_id))
+        .and_where(Expr::col((table, entry_column)).in_subquery(entry_ids(condition)))
+        .and_where(Expr::expr(value.clone()).is_not_null())
+        .group_by_columns([Alias::new("facet_value")])
+        .order_by(Alias::new("count"), Order::Desc)
+        .order_by(Alias::new("facet_value"), Order::Asc)
+        .to_owned()
+}
+
+#[derive(GraphQLEnum, Clone, Copy)]
+pub enum DateInterval {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl DateInterval {
+    // Field name understood by date_trunc
+    fn unit(self) -> &'static str {
+        match self {
+            DateInterval::Day => "day",
+            DateInterval::Week => "week",
+            DateInterval::Month => "month",
+            DateInterval::Year => "year",
+        }
+    }
+}
+
+#[derive(GraphQLObject)]
+#[graphql(context = crate::state::AppData)]
+pub struct EntryGroup {
+    pub key: Option<String>, // Text, "true"/"false", related entry id or bucket start; null for entries without a value
+    pub label: Option<String>, // Name of the related entry for Relation fields
+    pub count: i32, // Matching entries in the group, including those past the limit
+    pub entries: Vec<Entry>,
+}
+
+#[derive(FromQueryResult)]
+struct GroupedRow {
+    #[sea_orm(nested)]
+    entry: entities::entries::Model,
+    group_key: Option<String>,
+    group_count: i64,
+}
+
+// Group the matching entries by the value of field `by`, keeping the first
+// `limit_per_group` entries of each group in the order of `keys`. Relation
+// fields put an entry in the group of every target, DateTime fields group by
+// the start of the `interval` bucket.
+#[allow(clippy::too_many_arguments)]
+pub async fn group_entries(
+    db: &DatabaseConnection,
+    fields: &[Field],
+    condition: Condition,
+    by: &str,
+    interval: Option<DateInterval>,
+    keys: &[SortKey],
+    limit_per_group: i32,
+    group_order: Order,
+) -> FieldResult<Vec<EntryGroup>> {
+    let field = validate_field(fields, by, &[
+        DataTypes::Text,
+        DataTypes::Boolean,
+        DataTypes::Relation,
+        DataTypes::DateTime,
+    ])?;
+    let group_value = Alias::new("group_value");
+
+    let (table, join_on, value): (TableRef, Condition, SimpleExpr) = match field.data_type {
+        DataTypes::Text => group_join(
+            entities::entry_text_values::Entity,
+            entities::entry_text_values::Column::EntryId,
+            entities::entry_text_values::Column::FieldId,
+            entities::entry_text_values::Column::Value,
+            field.id,
+        ),
+        DataTypes::Boolean => group_join(
+            entities::entry_boolean_values::Entity,
+            entities::entry_boolean_values::Column::EntryId,
+            entities::entry_boolean_values::Column::FieldId,
+            entities::entry_boolean_values::Column::Value,
+            field.id,
+        ),
+        DataTypes::Relation => group_join(
+            entities::entry_relation_values::Entity,
+            entities::entry_relation_values::Column::FromEntryId,
+            entities::entry_relation_values::Column::FieldId,
+            entities::entry_relation_values::Column::ToEntryId,
+            field.id,
+        ),
+        DataTypes::DateTime => {
+            let Some(interval) = interval else {
+                return Err(juniper::FieldError::new(
+                    format!("interval is required to group by DateTime field '{}'", field.name),
+                    Value::null(),
+                ));
+            };
+            let (table, join_on, value) = group_join(
+                entities::entry_date_time_values::Entity,
+                entities::entry_date_time_values::Column::EntryId,
+                entities::entry_date_time_values::Column::FieldId,
+                entities::entry_date_time_values::Column::Value,
+                field.id,
+            );
+            let bucket = Expr::cust_with_exprs(format!("date_trunc('{}', $1)", interval.unit()), [value]);
+            (table, join_on, bucket)
+        }
+        _ => unreachable!(), // validate_field ensures correct types
+    };
+    let group_key = Expr::cust_with_exprs("CAST($1 AS text)", [value]);
+
+    // Number the entries of each group in the requested order
+    let window_order: Vec<String> = keys
+        .iter()
+        .enumerate()
+        .map(|(i, key)| {
+            let order = match key.order {
+                Order::Desc => "DESC",
+                _ => "ASC",
+            };
+            let nulls = match key.nulls {
+                NullOrdering::First => "FIRST",
+                NullOrdering::Last => "LAST",
+            };
+            format!("${} {} NULLS {}", i + 2, order, nulls)
+        })
+        .collect();
+    let partition = || std::iter::once(group_key.clone());
+    let group_rank = Expr::cust_with_exprs(
+        format!("row_number() OVER (PARTITION BY $1 ORDER BY {})", window_order.join(", ")),
+        partition().chain(keys.iter().map(|key| key.expr.clone())),
+    );
+    let group_count = Expr::cust_with_exprs("count(*) OVER (PARTITION BY $1)", partition());
+
+    let grouped = Query::select()
+        .column((entities::entries::Entity, Asterisk))
+        .expr_as(group_key.clone(), Alias::new("group_key"))
+        .expr_as(group_rank, Alias::new("group_rank"))
+        .expr_as(group_count, Alias::new("group_count"))
+        .from(entities::entries::Entity)
+        .join_as(JoinType::LeftJoin, table, group_value, join_on)
+        .cond_where(condition)
+        .to_owned();
+
+    let statement = Query::select()
+        .column(Asterisk)
+        .from_subquery(grouped, Alias::new("grouped"))
+        .and_where(Expr::col(Alias::new("group_rank")).lte(limit_per_group.clamp(1, 100)))
+        .order_by_with_nulls(Alias::new("group_key"), group_order, NullOrdering::Last)
+        .order_by(Alias::new("group_rank"), Order::Asc)
+        .to_owned();
+
+    let rows = GroupedRow::find_by_statement(db.get_database_backend().build(&statement))
+        .all(db)
+        .await?;
+
+    // Rows arrive ordered by group, so each new key starts a new group
+    let mut groups: Vec<EntryGroup> = Vec::new();
+    for row in rows {
+        match groups.last_mut() {
+            Some(group) if group.key == row.group_key => group.entries.push(Entry::from(row.entry)),
+            _ => groups.push(EntryGroup {
+                key: row.group_key,
+                label: None,
+                count: row.group_count as i32,
+                entries: vec![Entry::from(row.entry)],
+            }),
+        }
+    }
+
+    // Label relation targets with their entry names
+    if field.data_type == DataTypes::Relation {
+        let names = entry_names(db, groups.iter().filter_map(|g| g.key.as_deref())).await?;
+        for group in &mut groups {
+            group.label = group.key.as_ref().and_then(|key| names.get(key).cloned());
+        }
+    }
+
+    Ok(groups)
+}
+
+// Left join the value rows of `field_id` as "group_value", returning the
+// table, the join condition and the joined value column
+fn group_join<T, C>(table: T, entry_column: C, field_column: C, value_column: C, field_id: Uuid) -> (TableRef, Condition, SimpleExpr)
+where
+    T: Iden + Copy + 'static,
+    C: Iden + Copy + 'static,
+{
+    let group_value = Alias::new("group_value");
+    let join_on = Condition::all()
+        .add(Expr::col((group_value.clone(), entry_column)).equals((
+            entities::entries::Entity,
+            entities::entries::Column::Id,
+        )))
+        .add(Expr::col((group_value.clone(), field_column)).eq(field_id));
+    (table.into_table_ref(), join_on, Expr::col((group_value, value_column)).into())
+}
+
+#[derive(GraphQLObject)]
+pub struct HistogramBucket {
+    pub start: DateTime<Utc>,
+    pub count: i32,
+}
+
+#[derive(FromQueryResult)]
+struct BucketRow {
+    bucket_start: DateTime<Utc>,
+    count: i64,
+}
+
+// Count the matching entries per `interval` bucket of a DateTime field, or of
+// their creation date without one. Buckets start at midnight in `time_zone`
+// and only buckets holding entries are returned, oldest first.
+pub async fn histogram_entries(
+    db: &DatabaseConnection,
+    fields: &[Field],
+    condition: Condition,
+    field_name: Option<&str>,
+    interval: DateInterval,
+    time_zone: &str,
+) -> FieldResult<Vec<HistogramBucket>> {
+    validate_time_zone(db, time_zone).await?;
+
+    // Stored timestamps are UTC, so shift them into the zone before truncating
+    let bucket = |value: SimpleExpr| {
+        Expr::cust_with_exprs(
+            format!("date_trunc('{}', $1 AT TIME ZONE 'UTC' AT TIME ZONE $2) AT TIME ZONE $3", interval.unit()),
+            [value, Expr::val(time_zone).into(), Expr::val(time_zone).into()],
+        )
+    };
+
+    let mut statement = Query::select();
+    match field_name {
+        Some(name) => {
+            let field = validate_field(fields, name, &[DataTypes::DateTime])?;
+            let column = |c| Expr::col((entities::entry_date_time_values::Entity, c));
+            statement
+                .expr_as(bucket(column(entities::entry_date_time_values::Column::Value).into()), Alias::new("bucket_start"))
+                .expr_as(Func::count(column(entities::entry_date_time_values::Column::EntryId)), Alias::new("count"))
+                .from(entities::entry_date_time_values::Entity)
+                .and_where(column(entities::entry_date_time_values::Column::FieldId).eq(field.id))
+                .and_where(column(entities::entry_date_time_values::Column::EntryId).in_subquery(entry_ids(condition)))
+                .and_where(column(entities::entry_date_time_values::Column::Value).is_not_null());
+        }
+        None => {
+            let column = |c| Expr::col((entities::entries::Entity, c));
+            statement
+                .expr_as(bucket(column(entities::entries::Column::CreatedAt).into()), Alias::new("bucket_start"))
+                .expr_as(Func::count(column(entities::entries::Column::Id)), Alias::new("count"))
+                .from(entities::entries::Entity)
+                .cond_where(condition);
+        }
+    }
+    statement
+        .group_by_columns([Alias::new("bucket_start")])
+        .order_by(Alias::new("bucket_start"), Order::Asc);
+
+    let buckets = BucketRow::find_by_statement(db.get_database_backend().build(&statement))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|row| HistogramBucket {
+            start: row.bucket_start,
+            count: row.count as i32,
+        })
+        .collect();
+
+    Ok(buckets)
+}
+
+// Reject zones Postgres does not know before they reach AT TIME ZONE
+async fn validate_time_zone(db: &DatabaseConnection, time_zone: &str) -> FieldResult<()> {
+    let statement = Query::select()
+        .expr(Expr::val(1))
+        .from(Alias::new("pg_timezone_names"))
+        .and_where(Expr::col(Alias::new("name")).eq(time_zone))
+        .to_owned();
+    if db.query_one(db.get_database_backend().build(&statement)).await?.is_none() {
+        return Err(juniper::FieldError::new(
+            format!("Unknown time zone '{}', expected an IANA name such as \"Europe/Berlin\"", time_zone),
+            Value::null(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use entities::sea_orm_active_enums::DataTypes;
+use juniper::{FieldResult, GraphQLObject, Value};
+use sea_orm::{ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, sea_query::Query};
+use uuid::Uuid;
+
+use super::collection::Field;
+use super::entries::Entry;
+use super::filters::{EntryFilters, entry_condition, validate_field};
+use super::ordering::{EntrySort, apply_sorts};
+use crate::state::AppData;
+
+// Entries pointing at one entry through one relation field
+#[derive(GraphQLObject)]
+#[graphql(context = crate::state::AppData)]
+pub struct Backlinks {
+    pub field: Field,
+    pub total_count: i32,
+    pub entries: Vec<Entry>,
+}
+
+// Which incoming references to load and how to page through them
+pub struct BacklinkArgs {
+    pub collection: Option<String>,
+    pub field: Option<String>,
+    pub filters: Option<EntryFilters>,
+    pub order_by: Option<Vec<EntrySort>>,
+    pub page: Option<i32>,
+    pub page_size: Option<i32>,
+}
+
+// Entries whose relation fields point at `entry_id`, one group per relation
+// field. Without a collection every field with references makes a group;
+// a named field always makes one, even if nothing points here through it.
+pub async fn load_backlinks(ctx: &AppData, entry_id: Uuid, args: BacklinkArgs) -> FieldResult<Vec<Backlinks>> {
+    let db = &ctx.db;
+    let page_num = args.page.unwrap_or(1).max(1);
+    let page_size = args.page_size.unwrap_or(10).clamp(1, 100);
+
+    // Field names and filters only mean something within a collection
+    let collection = match &args.collection {
+        Some(name) => Some(
+            entities::collections::Entity::find()
+                .filter(entities::collections::Column::Name.eq(name.as_str()))
+                .one(db)
+                .await?
+                .ok_or_else(|| juniper::FieldError::new(
+                    format!("Collection '{}' does not exist", name),
+                    Value::null(),
+                ))?,
+        ),
+        None if args.field.is_some() || args.filters.is_some() => {
+            return Err(juniper::FieldError::new(
+                "field and filters need a collection".to_string(),
+                Value::null(),
+            ));
+        }
+        None => None,
+    };
+
+    let source_fields: Vec<entities::fields::Model> = match (&collection, &args.field) {
+        (Some(collection), Some(name)) => {
+            let collection_fields = ctx.loaders.fields(db, collection.id).await?;
+            let fields: Vec<Field> = collection_fields.iter().cloned().map(Field::from).collect();
+            let field_id = validate_field(&fields, name, &[DataTypes::Relation])?.id;
+            collection_fields.iter().filter(|f| f.id == field_id).cloned().collect()
+        }
+        _ => {
+            // Relation fields with at least one value pointing at the entry
+            let referencing = Query::select()
+                .column(entities::entry_relation_values::Column::FieldId)
+                .from(entities::entry_relation_values::Entity)
+                .and_where(entities::entry_relation_values::Column::ToEntryId.eq(entry_id))
+                .to_owned();
+            let mut query = entities::fields::Entity::find()
+                .filter(entities::fields::Column::Id.in_subquery(referencing));
+            if let Some(collection) = &collection {
+                query = query.filter(entities::fields::Column::CollectionId.eq(collection.id));
+            }
+            query
+                .order_by_asc(entities::fields::Column::CreatedAt)
+                .order_by_asc(entities::fields::Column::Id)
+                .all(db)
+                .await?
+        }
+    };
+
+    // With a collection the filters compile once and hold for every group
+    let filtered = match &collection {
+        Some(collection) => {
+            let fields: Vec<Field> = ctx.loaders.fields(db, collection.id).await?.iter().cloned().map(Field::from).collect();
+            Some(entry_condition(ctx, collection.id, &fields, args.filters).await?)
+        }
+        None => None,
+    };
+
+    let mut groups = Vec::with_capacity(source_fields.len());
+    for source_field in source_fields {
+        let fields: Vec<Field> = ctx
+            .loaders
+            .fields(db, source_field.collection_id)
+            .await?
+            .iter()
+            .cloned()
+            .map(Field::from)
+            .collect();
+        let condition = match &filtered {
+            Some(condition) => condition.clone(),
+            None => Condition::all().add(entities::entries::Column::CollectionId.eq(source_field.collection_id)),
+        };
+        let sources = Query::select()
+            .column(entities::entry_relation_values::Column::FromEntryId)
+            .from(entities::entry_relation_values::Entity)
+            .and_where(entities::entry_relation_values::Column::FieldId.eq(source_field.id))
+            .and_where(entities::entry_relation_values::Column::ToEntryId.eq(entry_id))
+            .to_owned();
+
+        let query = entities::entries::Entity::find()
+            .filter(condition)
+            .filter(entities::entries::Column::Id.in_subquery(sources));
+        let paginator = apply_sorts(query, &fields, args.order_by.clone())?.paginate(db, page_size as u64);
+        let total_count = paginator.num_items().await? as i32;
+        let entries = paginator.fetch_page(page_num as u64 - 1).await?;
+
+        groups.push(Backlinks {
+            field: Field::from(source_field),
+            total_count,
+            entries: entries.into_iter().map(Entry::from).collect(),
+        });
+    }
+
+    let ids = groups.iter().flat_map(|g| g.entries.iter().map(|e| e.id)).collect();
+    ctx.loaders.prime(ids).await;
+
+    Ok(groups)
+}
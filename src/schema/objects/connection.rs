@@ -0,0 +1,398 @@
+use juniper::{FieldResult, GraphQLObject, Value};
+use sea_orm::{
+    Condition, DatabaseConnection, DbErr, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter, QueryResult,
+    QuerySelect,
+    sea_query::{Expr, NullOrdering, Order, SimpleExpr},
+};
+use serde::{Deserialize, Serialize};
+
+use super::ordering::{SortKey, order_by_keys};
+
+#[derive(GraphQLObject)]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub has_previous_page: bool,
+    pub start_cursor: Option<String>,
+    pub end_cursor: Option<String>,
+}
+
+// Offset arguments (page, page_size) or Relay arguments (first/after,
+// last/before) of a paginated field; only one kind may be given
+pub struct PageArgs {
+    pub page: Option<i32>,
+    pub page_size: Option<i32>,
+    pub first: Option<i32>,
+    pub after: Option<String>,
+    pub last: Option<i32>,
+    pub before: Option<String>,
+}
+
+impl PageArgs {
+    fn by_cursor(&self) -> bool {
+        self.first.is_some() || self.after.is_some() || self.last.is_some() || self.before.is_some()
+    }
+}
+
+// One page of rows with their cursors. `index` is the page number when paging
+// by offset and 0 when paging by cursor.
+pub struct Page<M> {
+    pub items: Vec<(M, String)>,
+    pub page_info: PageInfo,
+    pub num_items: i32,
+    pub num_pages: i32,
+    pub index: i32,
+    pub size: i32,
+}
+
+// Decoded cursor: the ordering it was issued for and the sort key values of its row
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    order: String,
+    keys: Vec<Option<String>>,
+}
+
+// A row together with the text values of its sort keys
+struct CursorRow<M> {
+    item: M,
+    keys: serde_json::Value,
+}
+
+impl<M: FromQueryResult> FromQueryResult for CursorRow<M> {
+    fn from_query_result(res: &QueryResult, pre: &str) -> Result<Self, DbErr> {
+        Ok(CursorRow {
+            item: M::from_query_result(res, pre)?,
+            keys: res.try_get(pre, "cursor_keys")?,
+        })
+    }
+}
+
+impl<M> Page<M> {
+    pub fn map<T>(self, f: impl Fn(M) -> T) -> Page<T> {
+        Page {
+            items: self.items.into_iter().map(|(item, cursor)| (f(item), cursor)).collect(),
+            page_info: self.page_info,
+            num_items: self.num_items,
+            num_pages: self.num_pages,
+            index: self.index,
+            size: self.size,
+        }
+    }
+}
+
+// Load one page of `query` in the order of `keys`, which must end with a
+// unique key, read as `M`. Rows come back with their cursors, so that a client
+// can switch from page numbers to cursors at any point.
+pub async fn load_page<E, M>(
+    db: &DatabaseConnection,
+    query: sea_orm::Select<E>,
+    keys: &[SortKey],
+    args: PageArgs,
+) -> FieldResult<Page<M>>
+where
+    E: EntityTrait,
+    M: FromQueryResult + Send + Sync,
+{
+    if !args.by_cursor() {
+        let index = args.page.unwrap_or(1).max(1);
+        let size = args.page_size.unwrap_or(10).clamp(1, 100);
+        let order = order_signature(keys);
+
+        let paginator = order_by_keys(query, keys)
+            .column_as(cursor_keys_expr(keys), "cursor_keys")
+            .into_model::<CursorRow<M>>()
+            .paginate(db, size as u64);
+        let rows = paginator.fetch_page(index as u64 - 1).await?;
+        let counts = paginator.num_items_and_pages().await?;
+
+        let mut items = Vec::with_capacity(rows.len());
+        for row in rows {
+            let keys: Vec<Option<String>> = serde_json::from_value(row.keys)?;
+            items.push((row.item, encode_cursor(&order, keys)?));
+        }
+        let num_pages = counts.number_of_pages as i32;
+
+        return Ok(Page {
+            page_info: PageInfo {
+                has_next_page: index < num_pages,
+                has_previous_page: index > 1,
+                start_cursor: items.first().map(|(_, cursor)| cursor.clone()),
+                end_cursor: items.last().map(|(_, cursor)| cursor.clone()),
+            },
+            items,
+            num_items: counts.number_of_items as i32,
+            num_pages,
+            index,
+            size,
+        });
+    }
+
+    if args.page.is_some() || args.page_size.is_some() {
+        return Err(juniper::FieldError::new(
+            "page and page_size cannot be combined with first, after, last or before".to_string(),
+            Value::null(),
+        ));
+    }
+    let size = args.first.or(args.last).unwrap_or(10).clamp(1, 100);
+    let num_items = query.clone().into_model::<M>().count(db).await? as i32;
+    let (items, page_info) = load_connection(db, query, keys, args).await?;
+
+    Ok(Page {
+        items,
+        page_info,
+        num_items,
+        num_pages: (num_items + size - 1) / size,
+        index: 0,
+        size,
+    })
+}
+
+// Load the page of `query` that the Relay arguments of `args` select
+async fn load_connection<E, M>(
+    db: &DatabaseConnection,
+    mut query: sea_orm::Select<E>,
+    keys: &[SortKey],
+    args: PageArgs,
+) -> FieldResult<(Vec<(M, String)>, PageInfo)>
+where
+    E: EntityTrait,
+    M: FromQueryResult,
+{
+    let order = order_signature(keys);
+    let reversed: Vec<SortKey> = keys.iter().map(SortKey::reversed).collect();
+
+    if let Some(after) = &args.after {
+        query = query.filter(keyset_condition(keys, &decode_cursor(after, &order, keys.len())?));
+    }
+    // Rows before a cursor are the rows after it in the reversed order
+    if let Some(before) = &args.before {
+        query = query.filter(keyset_condition(&reversed, &decode_cursor(before, &order, keys.len())?));
+    }
+
+    // `last` walks the reversed order from the end, then flips the page back
+    let (backward, size) = match (args.first, args.last) {
+        (Some(_), Some(_)) => {
+            return Err(juniper::FieldError::new(
+                "Only one of first and last may be given".to_string(),
+                Value::null(),
+            ));
+        }
+        (None, Some(last)) => (true, last),
+        (first, None) => (false, first.unwrap_or(10)),
+    };
+    let size = size.clamp(1, 100) as usize;
+
+    let rows = order_by_keys(query, if backward { &reversed } else { keys })
+        .column_as(cursor_keys_expr(keys), "cursor_keys")
+        .limit(size as u64 + 1)
+        .into_model::<CursorRow<M>>()
+        .all(db)
+        .await?;
+    let has_more = rows.len() > size;
+
+    let mut items = Vec::with_capacity(size);
+    for row in rows.into_iter().take(size) {
+        let keys: Vec<Option<String>> = serde_json::from_value(row.keys)?;
+        items.push((row.item, encode_cursor(&order, keys)?));
+    }
+    if backward {
+        items.reverse();
+    }
+
+    let page_info = PageInfo {
+        has_next_page: if backward { args.before.is_some() } else { has_more },
+        has_previous_page: if backward { has_more } else { args.after.is_some() },
+        start_cursor: items.first().map(|(_, cursor)| cursor.clone()),
+        end_cursor: items.last().map(|(_, cursor)| cursor.clone()),
+    };
+
+    Ok((items, page_info))
+}
+
+// Describe an ordering so that cursors issued for another one are rejected
+fn order_signature(keys: &[SortKey]) -> String {
+    keys.iter()
+        .map(|key| {
+            let order = match key.order {
+                Order::Desc => "desc",
+                _ => "asc",
+            };
+            let nulls = match key.nulls {
+                NullOrdering::First => "first",
+                NullOrdering::Last => "last",
+            };
+            format!("{} {} nulls {}", key.name, order, nulls)
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// JSON array of every sort key of the row as text
+fn cursor_keys_expr(keys: &[SortKey]) -> SimpleExpr {
+    let placeholders: Vec<String> = (1..=keys.len()).map(|i| format!("CAST(${} AS text)", i)).collect();
+    Expr::cust_with_exprs(
+        format!("jsonb_build_array({})", placeholders.join(", ")),
+        keys.iter().map(|key| key.expr.clone()),
+    )
+}
+
+// Rows strictly after the cursor values in the order of `keys`:
+// (k1 after v1) OR (k1 = v1 AND k2 after v2) OR ...
+fn keyset_condition(keys: &[SortKey], values: &[Option<String>]) -> Condition {
+    let mut condition = Condition::any();
+    let mut equal = Condition::all();
+
+    for (key, value) in keys.iter().zip(values) {
+        let column = || Expr::expr(key.expr.clone());
+        let typed = || Expr::cust_with_exprs(format!("CAST($1 AS {})", key.cast), [Expr::val(value.clone()).into()]);
+
+        let after = match (value, &key.nulls) {
+            // Nothing sorts after a trailing NULL
+            (None, NullOrdering::Last) => None,
+            (None, NullOrdering::First) => Some(column().is_not_null()),
+            (Some(_), nulls) => {
+                let beyond = match key.order {
+                    Order::Desc => column().lt(typed()),
+                    _ => column().gt(typed()),
+                };
+                Some(match nulls {
+                    NullOrdering::Last => beyond.or(column().is_null()),
+                    NullOrdering::First => beyond,
+                })
+            }
+        };
+        if let Some(after) = after {
+            condition = condition.add(equal.clone().add(after));
+        }
+
+        equal = equal.add(match value {
+            None => column().is_null(),
+            Some(_) => column().eq(typed()),
+        });
+    }
+
+    condition
+}
+
+fn encode_cursor(order: &str, keys: Vec<Option<String>>) -> FieldResult<String> {
+    let json = serde_json::to_vec(&Cursor {
+        order: order.to_string(),
+        keys,
+    })?;
+    Ok(json.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn decode_cursor(cursor: &str, order: &str, len: usize) -> FieldResult<Vec<Option<String>>> {
+    let invalid = || juniper::FieldError::new(format!("Invalid cursor '{}'", cursor), Value::null());
+
+    if !cursor.len().is_multiple_of(2) || !cursor.is_ascii() {
+        return Err(invalid());
+    }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&cursor[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| invalid())?;
+    let decoded: Cursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+
+    if decoded.order != order || decoded.keys.len() != len {
+        return Err(juniper::FieldError::new(
+            "Cursor was issued for a different order".to_string(),
+            Value::null(),
+        ));
+    }
+
+    Ok(decoded.keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::sea_query::{Alias, PostgresQueryBuilder, Query};
+
+    fn key(name: &str, order: Order, nulls: NullOrdering) -> SortKey {
+        SortKey {
+            name: name.to_string(),
+            expr: Expr::col(Alias::new(name)).into(),
+            order,
+            nulls,
+            cast: "text",
+        }
+    }
+
+    fn sql(condition: Condition) -> String {
+        Query::select()
+            .column(Alias::new("id"))
+            .from(Alias::new("t"))
+            .cond_where(condition)
+            .to_string(PostgresQueryBuilder)
+    }
+
+    fn values(values: &[Option<&str>]) -> Vec<Option<String>> {
+        values.iter().map(|v| v.map(str::to_string)).collect()
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let keys = values(&[Some("2024-02-01 04:00:00"), None, Some("e0000000-0000-0000-0000-000000000004")]);
+        let cursor = encode_cursor("key:createdAt asc nulls last", keys.clone()).unwrap();
+        assert!(cursor.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(decode_cursor(&cursor, "key:createdAt asc nulls last", 3).unwrap(), keys);
+    }
+
+    #[test]
+    fn cursor_for_another_order_is_rejected() {
+        let cursor = encode_cursor("key:name asc nulls last", values(&[Some("a")])).unwrap();
+        assert!(decode_cursor(&cursor, "key:name desc nulls last", 1).is_err());
+        assert!(decode_cursor(&cursor, "key:name asc nulls last", 2).is_err());
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        for cursor in ["", "abc", "zz", "7b7d", "éé"] {
+            assert!(decode_cursor(cursor, "id asc nulls last", 1).is_err(), "'{}' should be rejected", cursor);
+        }
+    }
+
+    #[test]
+    fn keyset_after_value_with_nulls_last() {
+        let keys = [key("a", Order::Asc, NullOrdering::Last), key("id", Order::Asc, NullOrdering::Last)];
+        assert_eq!(
+            sql(keyset_condition(&keys, &values(&[Some("x"), Some("1")]))),
+            r#"SELECT "id" FROM "t" WHERE "a" > (CAST('x' AS text)) OR "a" IS NULL OR ("a" = (CAST('x' AS text)) AND ("id" > (CAST('1' AS text)) OR "id" IS NULL))"#
+        );
+        // Only other trailing NULLs follow a NULL
+        assert_eq!(
+            sql(keyset_condition(&keys, &values(&[None, Some("1")]))),
+            r#"SELECT "id" FROM "t" WHERE "a" IS NULL AND ("id" > (CAST('1' AS text)) OR "id" IS NULL)"#
+        );
+    }
+
+    #[test]
+    fn keyset_after_value_with_nulls_first() {
+        let keys = [key("a", Order::Desc, NullOrdering::First), key("id", Order::Asc, NullOrdering::Last)];
+        assert_eq!(
+            sql(keyset_condition(&keys, &values(&[Some("x"), Some("1")]))),
+            r#"SELECT "id" FROM "t" WHERE "a" < (CAST('x' AS text)) OR ("a" = (CAST('x' AS text)) AND ("id" > (CAST('1' AS text)) OR "id" IS NULL))"#
+        );
+        // Every value follows a leading NULL
+        assert_eq!(
+            sql(keyset_condition(&keys, &values(&[None, Some("1")]))),
+            r#"SELECT "id" FROM "t" WHERE "a" IS NOT NULL OR ("a" IS NULL AND ("id" > (CAST('1' AS text)) OR "id" IS NULL))"#
+        );
+    }
+
+    #[test]
+    fn keyset_on_reversed_keys() {
+        let keys = [key("a", Order::Desc, NullOrdering::First), key("id", Order::Asc, NullOrdering::Last)];
+        let reversed: Vec<SortKey> = keys.iter().map(SortKey::reversed).collect();
+        assert_eq!(
+            sql(keyset_condition(&reversed, &values(&[Some("x"), Some("1")]))),
+            r#"SELECT "id" FROM "t" WHERE "a" > (CAST('x' AS text)) OR "a" IS NULL OR ("a" = (CAST('x' AS text)) AND "id" < (CAST('1' AS text)))"#
+        );
+        assert_eq!(
+            sql(keyset_condition(&reversed, &values(&[None, Some("1")]))),
+            r#"SELECT "id" FROM "t" WHERE "a" IS NULL AND "id" < (CAST('1' AS text))"#
+        );
+        assert_eq!(order_signature(&reversed), "a asc nulls last,id desc nulls first");
+    }
+}
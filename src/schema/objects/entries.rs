@@ -1,65 +1,158 @@
 use chrono::{DateTime, Utc};
-use entities::{fields, sea_orm_active_enums::DataTypes};
-use juniper::{
-    FieldResult, GraphQLInputObject, GraphQLObject, GraphQLScalar, GraphQLUnion, graphql_object,
-};
-use sea_orm::{
-    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
-    Statement, TransactionTrait,
-};
-use typst_as_lib::TypstEngine;
+use entities::sea_orm_active_enums::DataTypes;
+use juniper::{GraphQLObject, GraphQLUnion, LookAheadSelection, ScalarValue, graphql_object};
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
 use uuid::Uuid;
 
-pub struct EntryRelation {
-    pub from_entry_id: Uuid,
-    pub to_entry_id: Uuid,
+use super::{
+    backlinks::{BacklinkArgs, Backlinks, load_backlinks},
+    collection::Field,
+    filters::{EntryFilters, entry_condition},
+    ordering::{EntrySort, apply_sorts},
+    search::{SearchHit, SearchMatch},
+};
+use crate::loaders::{EntryPrefetch, EntryValues};
+
+// Targets of a relation field of one entry
+pub struct RelationListValue {
+    pub to_entry_ids: Vec<Uuid>,
 }
 
 #[graphql_object(context = crate::state::AppData)]
-impl EntryRelation {
-    async fn from_entry(
+impl RelationListValue {
+    // Number of related entries matching the filters, all of them without filters
+    async fn total_count(
         &self,
         context: &crate::state::AppData,
-    ) -> juniper::FieldResult<Option<Entry>> {
-        let db = &context.db;
-        let entry = entities::entries::Entity::find_by_id(self.from_entry_id)
-            .one(db)
-            .await?;
-        if let Some(entry) = entry {
-            Ok(Some(Entry {
-                id: entry.id,
-                created_at: entry.created_at.and_utc(),
-                collection_id: entry.collection_id,
-                created_by: entry.created_by,
-                name: entry.name,
-            }))
-        } else {
-            Ok(None)
+        filters: Option<EntryFilters>,
+    ) -> juniper::FieldResult<i32> {
+        if filters.is_none() {
+            return Ok(self.to_entry_ids.len() as i32);
         }
+        let query = self.target_query(context, filters, None).await?;
+        Ok(query.count(&context.db).await? as i32)
     }
 
-    async fn to_entry(
+    // Related entries, ordered by creation date unless order_by is given
+    async fn entries(
         &self,
         context: &crate::state::AppData,
-    ) -> juniper::FieldResult<Option<Entry>> {
-        let db = &context.db;
-        let entry = entities::entries::Entity::find_by_id(self.to_entry_id)
-            .one(db)
-            .await?;
-        if let Some(entry) = entry {
-            Ok(Some(Entry {
-                id: entry.id,
-                created_at: entry.created_at.and_utc(),
-                collection_id: entry.collection_id,
-                created_by: entry.created_by,
-                name: entry.name,
-            }))
-        } else {
-            Ok(None)
+        filters: Option<EntryFilters>,
+        order_by: Option<Vec<EntrySort>>,
+        page: Option<i32>,
+        page_size: Option<i32>,
+    ) -> juniper::FieldResult<Vec<Entry>> {
+        let page_num = page.unwrap_or(1).max(1);
+        let page_size = page_size.unwrap_or(10).clamp(1, 100);
+
+        // Without filters or ordering the targets come from the request's
+        // entry cache, which the look-ahead may already have filled
+        if filters.is_none() && order_by.is_none() {
+            let mut targets = context.loaders.entries(&context.db, &self.to_entry_ids).await?;
+            targets.sort_by_key(|e| (e.created_at, e.id));
+            return Ok(targets
+                .into_iter()
+                .skip(((page_num - 1) * page_size) as usize)
+                .take(page_size as usize)
+                .map(Entry::from)
+                .collect());
         }
+
+        let query = self.target_query(context, filters, order_by).await?;
+        let items = query
+            .paginate(&context.db, page_size as u64)
+            .fetch_page(page_num as u64 - 1)
+            .await?;
+        Ok(items.into_iter().map(Entry::from).collect())
     }
 }
 
+impl RelationListValue {
+    // Query over the related entries. Filters and field sorts apply to the
+    // fields of the targets' collection, so they need targets from a single one.
+    async fn target_query(
+        &self,
+        context: &crate::state::AppData,
+        filters: Option<EntryFilters>,
+        order_by: Option<Vec<EntrySort>>,
+    ) -> juniper::FieldResult<sea_orm::Select<entities::entries::Entity>> {
+        let db = &context.db;
+        let targets = context.loaders.entries(db, &self.to_entry_ids).await?;
+        let mut collection_ids: Vec<Uuid> = targets.iter().map(|e| e.collection_id).collect();
+        collection_ids.sort();
+        collection_ids.dedup();
+
+        let query = entities::entries::Entity::find()
+            .filter(entities::entries::Column::Id.is_in(self.to_entry_ids.clone()));
+        let (query, fields) = match collection_ids.as_slice() {
+            [collection_id] => {
+                let fields: Vec<Field> = context
+                    .loaders
+                    .fields(db, *collection_id)
+                    .await?
+                    .iter()
+                    .cloned()
+                    .map(Field::from)
+                    .collect();
+                let condition = entry_condition(context, *collection_id, &fields, filters).await?;
+                (query.filter(condition), fields)
+            }
+            _ if filters.is_some() => {
+                return Err(juniper::FieldError::new(
+                    "Related entries span several collections, so they cannot be filtered".to_string(),
+                    juniper::Value::null(),
+                ));
+            }
+            _ => (query, Vec::new()),
+        };
+
+        apply_sorts(query, &fields, order_by)
+    }
+}
+
+// What the selection sets of an entry ask for besides the entry row
+pub fn entry_prefetch<'a, S: ScalarValue + 'a>(
+    selections: impl IntoIterator<Item = LookAheadSelection<'a, S>>,
+) -> EntryPrefetch {
+    selections
+        .into_iter()
+        .map(selection_prefetch)
+        .fold(EntryPrefetch::default(), |a, b| EntryPrefetch {
+            values: a.values || b.values,
+            related_entries: a.related_entries || b.related_entries,
+        })
+}
+
+fn selection_prefetch<S: ScalarValue>(entry: LookAheadSelection<'_, S>) -> EntryPrefetch {
+    let values = selected_children(entry, "values");
+    let related_entries = values
+        .iter()
+        .flat_map(|values| selected_children(*values, "value"))
+        .chain(selected_children(entry, "value"))
+        .any(|value| !selected_children(value, "entries").is_empty());
+
+    // The single field accessors share the batch as well
+    let accessors = ["value", "text", "number", "boolean", "dateTime", "textList", "numberList"];
+    EntryPrefetch {
+        values: !values.is_empty()
+            || entry.children().iter().any(|child| accessors.contains(&child.field_original_name())),
+        related_entries,
+    }
+}
+
+// Children of a selection that select the field `name`, under any alias
+pub fn selected_children<'a, S: ScalarValue>(
+    selection: LookAheadSelection<'a, S>,
+    name: &str,
+) -> Vec<LookAheadSelection<'a, S>> {
+    selection
+        .children()
+        .iter()
+        .filter(|child| child.field_original_name() == name)
+        .copied()
+        .collect()
+}
+
 #[derive(GraphQLObject)]
 pub struct TypstText {
     pub raw: String,
@@ -108,7 +201,7 @@ pub enum ValueType {
     TypstText(TypstText),
     Boolean(BooleanValue),
     Number(NumberValue),
-    Relation(EntryRelation),
+    Relation(RelationListValue),
     DateTime(DateTimeValue),
     TextList(TextListValue),
     NumberList(NumberListValue),
@@ -116,136 +209,70 @@ pub enum ValueType {
 }
 
 impl ValueType {
-    async fn from_data_type(
-        data_type: &entities::sea_orm_active_enums::DataTypes,
-        entry_id: Uuid,
-        field_id: Uuid,
-        context: &crate::state::AppData,
-    ) -> juniper::FieldResult<Option<ValueType>> {
-        let db = &context.db;
-        match data_type {
-            entities::sea_orm_active_enums::DataTypes::Text => {
-                let v = entities::entry_text_values::Entity::find()
-                    .filter(entities::entry_text_values::Column::EntryId.eq(entry_id))
-                    .filter(entities::entry_text_values::Column::FieldId.eq(field_id))
-                    .one(db)
-                    .await?;
-                if let Some(v) = v {
-                    Ok(Some(ValueType::Text(TextValue { value: v.value })))
-                } else {
-                    Ok(None)
-                }
-            }
-            entities::sea_orm_active_enums::DataTypes::TypstText => {
-                let v = entities::entry_typst_text_values::Entity::find()
-                    .filter(entities::entry_typst_text_values::Column::EntryId.eq(entry_id))
-                    .filter(entities::entry_typst_text_values::Column::FieldId.eq(field_id))
-                    .one(db)
-                    .await?;
-                if let Some(v) = v {
-                    Ok(Some(ValueType::TypstText(TypstText {
-                        raw: v.raw,
-                        rendered: v.rendered,
-                    })))
-                } else {
-                    Ok(None)
-                }
-            }
-            entities::sea_orm_active_enums::DataTypes::Boolean => {
-                let v = entities::entry_boolean_values::Entity::find()
-                    .filter(entities::entry_boolean_values::Column::EntryId.eq(entry_id))
-                    .filter(entities::entry_boolean_values::Column::FieldId.eq(field_id))
-                    .one(db)
-                    .await?;
-                if let Some(v) = v {
-                    Ok(Some(ValueType::Boolean(BooleanValue { value: v.value })))
-                } else {
-                    Ok(None)
-                }
-            }
-            entities::sea_orm_active_enums::DataTypes::Number => {
-                let v = entities::entry_number_values::Entity::find()
-                    .filter(entities::entry_number_values::Column::EntryId.eq(entry_id))
-                    .filter(entities::entry_number_values::Column::FieldId.eq(field_id))
-                    .one(db)
-                    .await?;
-                if let Some(v) = v {
-                    Ok(Some(ValueType::Number(NumberValue { value: v.value })))
-                } else {
-                    Ok(None)
-                }
-            }
-            entities::sea_orm_active_enums::DataTypes::Relation => {
-                let v = entities::entry_relation_values::Entity::find()
-                    .filter(entities::entry_relation_values::Column::FromEntryId.eq(entry_id))
-                    .filter(entities::entry_relation_values::Column::FieldId.eq(field_id))
-                    .one(db)
-                    .await?;
-                if let Some(v) = v {
-                    Ok(Some(ValueType::Relation(EntryRelation {
-                        from_entry_id: v.from_entry_id,
-                        to_entry_id: v.to_entry_id,
-                    })))
-                } else {
-                    Ok(None)
-                }
-            }
-            entities::sea_orm_active_enums::DataTypes::DateTime => {
-                let v = entities::entry_date_time_values::Entity::find()
-                    .filter(entities::entry_date_time_values::Column::EntryId.eq(entry_id))
-                    .filter(entities::entry_date_time_values::Column::FieldId.eq(field_id))
-                    .one(db)
-                    .await?;
-                if let Some(v) = v {
-                    Ok(Some(ValueType::DateTime(DateTimeValue {
-                        value: v.value.map(|dt| dt.and_utc()),
-                    })))
-                } else {
-                    Ok(None)
-                }
-            }
-            entities::sea_orm_active_enums::DataTypes::TextList => {
-                let model = entities::entry_text_list_values::Entity::find()
-                    .filter(entities::entry_text_list_values::Column::EntryId.eq(entry_id))
-                    .filter(entities::entry_text_list_values::Column::FieldId.eq(field_id))
-                    .one(db)
-                    .await?;
-                if let Some(value) = model {
-                    Ok(Some(ValueType::TextList(TextListValue {
-                        value: value.value.unwrap_or_default(),
-                    })))
-                } else {
-                    Ok(None)
-                }
-            }
-            entities::sea_orm_active_enums::DataTypes::NumberList => {
-                let model = entities::entry_number_list_values::Entity::find()
-                    .filter(entities::entry_number_list_values::Column::EntryId.eq(entry_id))
-                    .filter(entities::entry_number_list_values::Column::FieldId.eq(field_id))
-                    .one(db)
-                    .await?;
-                if let Some(value) = model {
-                    Ok(Some(ValueType::NumberList(NumberListValue {
-                        value: value.value.unwrap_or_default(),
-                    })))
-                } else {
-                    Ok(None)
-                }
-            }
-            entities::sea_orm_active_enums::DataTypes::Object => {
-                let v = entities::entry_object_values::Entity::find()
-                    .filter(entities::entry_object_values::Column::EntryId.eq(entry_id))
-                    .filter(entities::entry_object_values::Column::FieldId.eq(field_id))
-                    .one(db)
-                    .await?;
-                if let Some(v) = v {
-                    Ok(Some(ValueType::Object(EntryObject {
-                        value: v.value.to_string(),
-                    })))
-                } else {
-                    Ok(None)
-                }
+    // Value of a field among the value rows of an entry
+    fn from_values(field: &entities::fields::Model, values: &EntryValues) -> Option<ValueType> {
+        match field.data_type {
+            DataTypes::Text => values
+                .text
+                .iter()
+                .find(|v| v.field_id == field.id)
+                .map(|v| ValueType::Text(TextValue { value: v.value.clone() })),
+            DataTypes::TypstText => values
+                .typst_text
+                .iter()
+                .find(|v| v.field_id == field.id)
+                .map(|v| ValueType::TypstText(TypstText {
+                    raw: v.raw.clone(),
+                    rendered: v.rendered.clone(),
+                })),
+            DataTypes::Boolean => values
+                .boolean
+                .iter()
+                .find(|v| v.field_id == field.id)
+                .map(|v| ValueType::Boolean(BooleanValue { value: v.value })),
+            DataTypes::Number => values
+                .number
+                .iter()
+                .find(|v| v.field_id == field.id)
+                .map(|v| ValueType::Number(NumberValue { value: v.value })),
+            // A relation field may point at several entries
+            DataTypes::Relation => {
+                let to_entry_ids: Vec<Uuid> = values
+                    .relation
+                    .iter()
+                    .filter(|v| v.field_id == field.id)
+                    .map(|v| v.to_entry_id)
+                    .collect();
+                (!to_entry_ids.is_empty()).then_some(ValueType::Relation(RelationListValue { to_entry_ids }))
             }
+            DataTypes::DateTime => values
+                .date_time
+                .iter()
+                .find(|v| v.field_id == field.id)
+                .map(|v| ValueType::DateTime(DateTimeValue {
+                    value: v.value.map(|dt| dt.and_utc()),
+                })),
+            DataTypes::TextList => values
+                .text_list
+                .iter()
+                .find(|v| v.field_id == field.id)
+                .map(|v| ValueType::TextList(TextListValue {
+                    value: v.value.clone().unwrap_or_default(),
+                })),
+            DataTypes::NumberList => values
+                .number_list
+                .iter()
+                .find(|v| v.field_id == field.id)
+                .map(|v| ValueType::NumberList(NumberListValue {
+                    value: v.value.clone().unwrap_or_default(),
+                })),
+            DataTypes::Object => values
+                .object
+                .iter()
+                .find(|v| v.field_id == field.id)
+                .map(|v| ValueType::Object(EntryObject {
+                    value: v.value.to_string(),
+                })),
         }
     }
 }
@@ -253,15 +280,42 @@ impl ValueType {
 #[derive(GraphQLObject)]
 #[graphql(context = crate::state::AppData)]
 pub struct FieldValue {
-    pub field: super::collection::Field,
+    pub field: Field,
     pub value: ValueType,
 }
+#[derive(Clone)]
 pub struct Entry {
     pub id: Uuid,
     pub created_at: DateTime<Utc>,
     pub collection_id: Uuid,
     pub created_by: Uuid,
     pub name: String,
+    pub search: Option<SearchMatch>,
+}
+
+impl From<entities::entries::Model> for Entry {
+    fn from(e: entities::entries::Model) -> Self {
+        Entry {
+            id: e.id,
+            created_at: e.created_at.and_utc(),
+            collection_id: e.collection_id,
+            created_by: e.created_by,
+            name: e.name,
+            search: None,
+        }
+    }
+}
+
+impl From<SearchHit> for Entry {
+    fn from(hit: SearchHit) -> Self {
+        Entry {
+            search: Some(SearchMatch {
+                rank: hit.search_rank as f64,
+                headline: hit.search_headline,
+            }),
+            ..Entry::from(hit.entry)
+        }
+    }
 }
 
 #[graphql_object(context = crate::state::AppData)]
@@ -278,32 +332,162 @@ impl Entry {
     fn name(&self) -> &str {
         &self.name
     }
+    // Rank and highlighted snippet, set when the entry was found by a search
+    fn search(&self) -> Option<&SearchMatch> {
+        self.search.as_ref()
+    }
+    // Values of every field, or only of the named ones
     async fn values(
         &self,
         context: &crate::state::AppData,
+        fields: Option<Vec<String>>,
     ) -> juniper::FieldResult<Vec<FieldValue>> {
         let db = &context.db;
 
-        let fields = entities::fields::Entity::find()
-            .filter(entities::fields::Column::CollectionId.eq(self.collection_id))
-            .all(db)
-            .await?;
-
-        let mut values = vec![];
-
-        for field in fields {
-            let v = ValueType::from_data_type(&field.data_type, self.id, field.id, context).await?;
-            if let Some(v) = v {
-                values.push(FieldValue { field: super::collection::Field {
-                    id: field.id,
-                    collection_id: field.collection_id,
-                    name: field.name,
-                    data_type: field.data_type,
-                    created_at: field.created_at.and_utc(),
-                }, value: v });
+        // Both are shared with the other entries of this request
+        let collection_fields = context.loaders.fields(db, self.collection_id).await?;
+        let (fields, entry_values) = match fields {
+            Some(names) => {
+                let fields = names
+                    .iter()
+                    .map(|name| find_field(&collection_fields, name, None))
+                    .collect::<juniper::FieldResult<Vec<_>>>()?;
+                let entry_values = context.loaders.field_values(db, self.id, &fields).await?;
+                (fields, entry_values)
             }
-        }
+            None => (
+                collection_fields.iter().collect(),
+                context.loaders.values(db, self.id).await?,
+            ),
+        };
+
+        let values = fields
+            .into_iter()
+            .filter_map(|field| {
+                ValueType::from_values(field, &entry_values).map(|value| FieldValue {
+                    field: Field::from(field.clone()),
+                    value,
+                })
+            })
+            .collect();
 
         Ok(values)
     }
+
+    // Entries whose relation fields point at this one, grouped by field.
+    // `collection` narrows the groups and is required by `field` and `filters`.
+    #[allow(clippy::too_many_arguments)]
+    async fn referenced_by(
+        &self,
+        context: &crate::state::AppData,
+        collection: Option<String>,
+        field: Option<String>,
+        filters: Option<EntryFilters>,
+        order_by: Option<Vec<EntrySort>>,
+        page: Option<i32>,
+        page_size: Option<i32>,
+    ) -> juniper::FieldResult<Vec<Backlinks>> {
+        let args = BacklinkArgs { collection, field, filters, order_by, page, page_size };
+        load_backlinks(context, self.id, args).await
+    }
+
+    // Value of a single field, null when the entry has none
+    async fn value(
+        &self,
+        context: &crate::state::AppData,
+        field: String,
+    ) -> juniper::FieldResult<Option<ValueType>> {
+        self.field_value(context, &field, None).await
+    }
+
+    async fn text(&self, context: &crate::state::AppData, field: String) -> juniper::FieldResult<Option<String>> {
+        match self.field_value(context, &field, Some(DataTypes::Text)).await? {
+            Some(ValueType::Text(text)) => Ok(text.value),
+            _ => Ok(None),
+        }
+    }
+
+    async fn number(&self, context: &crate::state::AppData, field: String) -> juniper::FieldResult<Option<f64>> {
+        match self.field_value(context, &field, Some(DataTypes::Number)).await? {
+            Some(ValueType::Number(number)) => Ok(number.value),
+            _ => Ok(None),
+        }
+    }
+
+    async fn boolean(&self, context: &crate::state::AppData, field: String) -> juniper::FieldResult<Option<bool>> {
+        match self.field_value(context, &field, Some(DataTypes::Boolean)).await? {
+            Some(ValueType::Boolean(boolean)) => Ok(boolean.value),
+            _ => Ok(None),
+        }
+    }
+
+    async fn date_time(
+        &self,
+        context: &crate::state::AppData,
+        field: String,
+    ) -> juniper::FieldResult<Option<DateTime<Utc>>> {
+        match self.field_value(context, &field, Some(DataTypes::DateTime)).await? {
+            Some(ValueType::DateTime(date_time)) => Ok(date_time.value),
+            _ => Ok(None),
+        }
+    }
+
+    async fn text_list(
+        &self,
+        context: &crate::state::AppData,
+        field: String,
+    ) -> juniper::FieldResult<Option<Vec<String>>> {
+        match self.field_value(context, &field, Some(DataTypes::TextList)).await? {
+            Some(ValueType::TextList(list)) => Ok(Some(list.value)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn number_list(
+        &self,
+        context: &crate::state::AppData,
+        field: String,
+    ) -> juniper::FieldResult<Option<Vec<f64>>> {
+        match self.field_value(context, &field, Some(DataTypes::NumberList)).await? {
+            Some(ValueType::NumberList(list)) => Ok(Some(list.value)),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Entry {
+    // Value of the named field, which must have the expected type if one is given
+    async fn field_value(
+        &self,
+        context: &crate::state::AppData,
+        name: &str,
+        expected: Option<DataTypes>,
+    ) -> juniper::FieldResult<Option<ValueType>> {
+        let db = &context.db;
+        let fields = context.loaders.fields(db, self.collection_id).await?;
+        let field = find_field(&fields, name, expected)?;
+        let values = context.loaders.field_values(db, self.id, &[field]).await?;
+        Ok(ValueType::from_values(field, &values))
+    }
+}
+
+fn find_field<'a>(
+    fields: &'a [entities::fields::Model],
+    name: &str,
+    expected: Option<DataTypes>,
+) -> juniper::FieldResult<&'a entities::fields::Model> {
+    let field = fields.iter().find(|f| f.name == name).ok_or_else(|| {
+        juniper::FieldError::new(
+            format!("Field '{}' does not exist in collection", name),
+            juniper::Value::null(),
+        )
+    })?;
+
+    match expected {
+        Some(expected) if field.data_type != expected => Err(juniper::FieldError::new(
+            format!("Field '{}' has type {:?}, expected {:?}", name, field.data_type, expected),
+            juniper::Value::null(),
+        )),
+        _ => Ok(field),
+    }
 }
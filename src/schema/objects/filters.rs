@@ -0,0 +1,1426 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use entities::sea_orm_active_enums::DataTypes;
+use juniper::{FieldResult, GraphQLEnum, GraphQLInputObject, Value};
+use sea_orm::{
+    ColumnTrait, Condition, EntityTrait, QueryFilter,
+    sea_query::{
+        BinOper, Expr, Iden, Query, SelectStatement, SimpleExpr,
+        extension::postgres::{PgBinOper, PgExpr},
+    },
+};
+use uuid::Uuid;
+
+use super::collection::Field;
+use super::search::typst_plain_text;
+use crate::{schema::relative_time, state::AppData};
+
+#[derive(GraphQLEnum, Debug)]
+pub enum TextComparison {
+    Eq,
+    Neq,
+    Contains,
+    StartsWith,
+    EndsWith,
+    EqIgnoreCase,
+    ContainsIgnoreCase,
+    StartsWithIgnoreCase,
+    EndsWithIgnoreCase,
+    Regex,           // POSIX regular expression
+    RegexIgnoreCase, // POSIX regular expression, case-insensitive
+    Similar,         // Trigram similarity at least similarity_threshold, needs pg_trgm
+    In,
+    NotIn,
+    IsNull,    // Value row present with a null value
+    IsNotNull, // Value row present with a value
+    Missing,   // No value row at all
+}
+
+#[derive(GraphQLEnum, Debug)]
+pub enum NumberComparison {
+    Eq,
+    Neq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Between,
+    In,
+    NotIn,
+    IsNull,    // Value row present with a null value
+    IsNotNull, // Value row present with a value
+    Missing,   // No value row at all
+}
+
+#[derive(GraphQLEnum, Debug)]
+pub enum BooleanComparison {
+    Eq,
+    Neq,
+    IsNull,    // Value row present with a null value
+    IsNotNull, // Value row present with a value
+    Missing,   // No value row at all
+}
+
+#[derive(GraphQLEnum, Debug)]
+pub enum DateTimeComparison {
+    Eq,
+    Neq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Between,
+    IsNull,    // Value row present with a null value
+    IsNotNull, // Value row present with a value
+    Missing,   // No value row at all
+}
+
+#[derive(GraphQLEnum, Debug)]
+pub enum ListComparison {
+    Contains,
+    ContainsAll,
+    ContainsAny,
+    IsEmpty,
+    IsNotEmpty,
+    LengthEq,
+    LengthGt,
+    LengthGte,
+    LengthLt,
+    LengthLte,
+    AnyGt,
+    AnyGte,
+    AnyLt,
+    AnyLte,
+}
+
+#[derive(GraphQLEnum, Debug)]
+pub enum RelationComparison {
+    ConnectedTo,    // Connected to one of the targets, same as ConnectedToAny
+    NotConnectedTo, // Connected to none of the targets
+    ConnectedToAny,
+    ConnectedToAll,
+    HasConnections,
+    HasNoConnections,
+}
+
+#[derive(GraphQLEnum)]
+pub enum BacklinkComparison {
+    HasBacklinks,
+    HasNoBacklinks,
+}
+
+#[derive(GraphQLEnum)]
+pub enum ObjectComparison {
+    HasProperty,
+    PropertyEquals,
+    PropertyContains,
+    IsEmpty,
+    IsNotEmpty,
+}
+
+// Specific filter types for each data type
+#[derive(GraphQLInputObject)]
+pub struct TextFilter {
+    pub field_name: String,
+    pub comparison: TextComparison,
+    pub value: Option<String>,
+    pub values: Option<Vec<String>>, // For In/NotIn
+    pub typst_source: Option<TypstTextSource>, // What TypstText fields are matched against, defaults to Raw
+    pub similarity_threshold: Option<f64>, // Minimum similarity for Similar, from 0 to 1, defaults to 0.3
+}
+
+#[derive(GraphQLEnum, Clone, Copy)]
+pub enum TypstTextSource {
+    Raw,      // The Typst source markup
+    Rendered, // Plain text extracted from the rendered HTML
+}
+
+#[derive(GraphQLInputObject)]
+pub struct NumberFilter {
+    pub field_name: String,
+    pub comparison: NumberComparison,
+    pub value: Option<f64>,
+    pub end_value: Option<f64>, // Inclusive upper bound for Between
+    pub values: Option<Vec<f64>>, // For In/NotIn
+}
+
+#[derive(GraphQLInputObject)]
+pub struct BooleanFilter {
+    pub field_name: String,
+    pub comparison: BooleanComparison,
+    pub value: Option<bool>,
+}
+
+#[derive(GraphQLInputObject)]
+pub struct DateTimeFilter {
+    pub field_name: String,
+    pub comparison: DateTimeComparison,
+    pub value: Option<DateTime<Utc>>,
+    pub relative_value: Option<String>, // Instead of value, e.g. "now-7d" or "startOfMonth"
+    pub end_value: Option<DateTime<Utc>>, // Inclusive upper bound for Between
+    pub relative_end_value: Option<String>, // Instead of end_value
+}
+
+#[derive(GraphQLInputObject)]
+pub struct ListFilter {
+    pub field_name: String,
+    pub comparison: ListComparison,
+    pub values: Option<Vec<String>>, // TextList values for Contains/ContainsAll/ContainsAny
+    pub number_values: Option<Vec<f64>>, // NumberList values for Contains/ContainsAll/ContainsAny
+    pub number_value: Option<f64>, // Bound for the NumberList AnyGt/AnyGte/AnyLt/AnyLte comparisons
+    pub length: Option<i32>, // For the Length* comparisons
+}
+
+#[derive(GraphQLInputObject)]
+pub struct RelationFilter {
+    pub field_name: String,
+    pub comparison: RelationComparison,
+    pub target_entry_id: Option<Uuid>, // Target for the ConnectedTo* and NotConnectedTo comparisons
+    pub target_entry_ids: Option<Vec<Uuid>>, // More targets by id
+    pub target_entries: Option<Vec<EntryReference>>, // More targets by collection and name
+    pub target_matches: Option<RelatedEntryFilters>, // Narrows every comparison to targets matching these filters
+}
+
+// Text comparison on the entry name, with the inputs of TextFilter
+#[derive(GraphQLInputObject)]
+pub struct NameFilter {
+    pub comparison: TextComparison,
+    pub value: Option<String>,
+    pub values: Option<Vec<String>>, // For In/NotIn
+    pub similarity_threshold: Option<f64>, // Minimum similarity for Similar, defaults to 0.3
+}
+
+// Filters on the entry row itself rather than its field values
+#[derive(GraphQLInputObject)]
+pub struct MetadataFilters {
+    pub name: Option<Vec<NameFilter>>,
+    pub created_by: Option<Vec<Uuid>>, // Created by any of these users
+    pub created_by_me: Option<bool>, // Created by the authenticated user
+    pub created_after: Option<DateTime<Utc>>, // Inclusive
+    pub relative_created_after: Option<String>, // Instead of created_after, e.g. "startOfWeek"
+    pub created_before: Option<DateTime<Utc>>, // Inclusive
+    pub relative_created_before: Option<String>, // Instead of created_before
+}
+
+// Relations pointing at the filtered entry, optionally only from entries of
+// one collection, through one of its fields, or from entries matching filters
+#[derive(GraphQLInputObject)]
+pub struct BacklinkFilter {
+    pub comparison: BacklinkComparison,
+    pub collection: Option<String>, // Collection of the linking entries
+    pub field_name: Option<String>, // Relation field of collection the links go through
+    pub source_matches: Option<EntryFilters>, // Filters the linking entries of collection must match
+}
+
+// An entry identified by its collection and name, as used in routes
+#[derive(GraphQLInputObject)]
+pub struct EntryReference {
+    pub collection: String,
+    pub name: String,
+}
+
+// Filters on the entries of another collection, reached through a relation
+#[derive(GraphQLInputObject)]
+pub struct RelatedEntryFilters {
+    pub collection: String,
+    pub filters: EntryFilters,
+}
+
+#[derive(GraphQLEnum, Clone, Copy)]
+pub enum PropertyValueType {
+    String,
+    Number,
+    Boolean,
+}
+
+#[derive(GraphQLInputObject)]
+pub struct ObjectFilter {
+    pub field_name: String,
+    pub comparison: ObjectComparison,
+    pub property_path: Option<String>, // JSON path like "address.city" or "links[0].url"
+    pub property_value: Option<String>, // Value to compare against
+    pub property_type: Option<PropertyValueType>, // How to interpret property_value, defaults to String
+}
+
+// Main filter input that accepts specific filter types, nestable with and/or/not
+#[derive(GraphQLInputObject)]
+pub struct EntryFilters {
+    pub metadata: Option<MetadataFilters>,
+    pub text_filters: Option<Vec<TextFilter>>,
+    pub number_filters: Option<Vec<NumberFilter>>,
+    pub boolean_filters: Option<Vec<BooleanFilter>>,
+    pub date_time_filters: Option<Vec<DateTimeFilter>>,
+    pub list_filters: Option<Vec<ListFilter>>,
+    pub relation_filters: Option<Vec<RelationFilter>>,
+    pub backlink_filters: Option<Vec<BacklinkFilter>>,
+    pub object_filters: Option<Vec<ObjectFilter>>,
+    pub and: Option<Vec<EntryFilters>>, // Every group must match
+    pub or: Option<Vec<EntryFilters>>, // At least one group must match, ignored when empty
+    pub not: Option<Box<EntryFilters>>, // The group must not match
+}
+
+// Collections named by nested relation filters, with their fields, and the
+// authenticated user for created_by_me
+pub struct FilterScope {
+    collections: HashMap<String, (Uuid, Vec<Field>)>,
+    user: Option<Uuid>,
+}
+
+impl FilterScope {
+    // Load every collection a filter tree reaches through relations
+    pub async fn load(ctx: &AppData, filters: &EntryFilters) -> FieldResult<Self> {
+        let db = &ctx.db;
+
+        let mut names = Vec::new();
+        collect_related_collections(filters, &mut names);
+
+        let mut collections = HashMap::new();
+        if !names.is_empty() {
+            let found = entities::collections::Entity::find()
+                .filter(entities::collections::Column::Name.is_in(names))
+                .all(db)
+                .await?;
+            let mut fields: HashMap<Uuid, Vec<Field>> = HashMap::new();
+            for f in entities::fields::Entity::find()
+                .filter(entities::fields::Column::CollectionId.is_in(found.iter().map(|c| c.id)))
+                .all(db)
+                .await?
+            {
+                fields.entry(f.collection_id).or_default().push(Field::from(f));
+            }
+            for c in found {
+                let collection_fields = fields.remove(&c.id).unwrap_or_default();
+                collections.insert(c.name, (c.id, collection_fields));
+            }
+        }
+
+        let user = ctx.claims.as_ref().and_then(|claims| Uuid::parse_str(&claims.sub).ok());
+
+        Ok(FilterScope { collections, user })
+    }
+
+    fn collection(&self, name: &str) -> FieldResult<&(Uuid, Vec<Field>)> {
+        self.collections.get(name).ok_or_else(|| juniper::FieldError::new(
+            format!("Collection '{}' does not exist", name),
+            Value::null(),
+        ))
+    }
+}
+
+fn collect_related_collections(filters: &EntryFilters, names: &mut Vec<String>) {
+    for filter in filters.relation_filters.iter().flatten() {
+        if let Some(related) = &filter.target_matches {
+            names.push(related.collection.clone());
+            collect_related_collections(&related.filters, names);
+        }
+    }
+    for filter in filters.backlink_filters.iter().flatten() {
+        if let Some(collection) = &filter.collection {
+            names.push(collection.clone());
+        }
+        if let Some(source) = &filter.source_matches {
+            collect_related_collections(source, names);
+        }
+    }
+    for group in filters.and.iter().flatten().chain(filters.or.iter().flatten()) {
+        collect_related_collections(group, names);
+    }
+    if let Some(group) = &filters.not {
+        collect_related_collections(group, names);
+    }
+}
+
+// Condition selecting the entries of a collection that match the optional filters
+pub async fn entry_condition(
+    ctx: &AppData,
+    collection_id: Uuid,
+    fields: &[Field],
+    filters: Option<EntryFilters>,
+) -> FieldResult<Condition> {
+    let mut condition = Condition::all().add(
+        Expr::col((entities::entries::Entity, entities::entries::Column::CollectionId)).eq(collection_id),
+    );
+    if let Some(filters) = filters {
+        let scope = FilterScope::load(ctx, &filters).await?;
+        condition = condition.add(compile_filters(&scope, fields, filters)?);
+    }
+    Ok(condition)
+}
+
+// Ids of the entries matching a condition on `entries`
+pub fn entry_ids(condition: Condition) -> SelectStatement {
+    Query::select()
+        .column((entities::entries::Entity, entities::entries::Column::Id))
+        .from(entities::entries::Entity)
+        .cond_where(condition)
+        .to_owned()
+}
+
+// Compile a filter tree into a single condition on `entries`.
+// Every leaf is a correlated EXISTS subquery, so filters on the same value
+// table never share a joined row and the result set stays distinct.
+pub fn compile_filters(scope: &FilterScope, fields: &[Field], filters: EntryFilters) -> FieldResult<Condition> {
+    let mut condition = Condition::all();
+
+    // Apply filters on the entry row
+    if let Some(metadata) = filters.metadata {
+        condition = condition.add(metadata_condition(scope, metadata)?);
+    }
+
+    // Apply text filters (includes both Text and TypstText)
+    if let Some(text_filters) = filters.text_filters {
+        for filter in text_filters {
+            condition = condition.add(text_filter_condition(fields, filter)?);
+        }
+    }
+
+    // Apply number filters
+    if let Some(number_filters) = filters.number_filters {
+        for filter in number_filters {
+            condition = condition.add(number_filter_condition(fields, filter)?);
+        }
+    }
+
+    // Apply boolean filters
+    if let Some(boolean_filters) = filters.boolean_filters {
+        for filter in boolean_filters {
+            condition = condition.add(boolean_filter_condition(fields, filter)?);
+        }
+    }
+
+    // Apply datetime filters
+    if let Some(datetime_filters) = filters.date_time_filters {
+        for filter in datetime_filters {
+            condition = condition.add(datetime_filter_condition(fields, filter)?);
+        }
+    }
+
+    // Apply list filters
+    if let Some(list_filters) = filters.list_filters {
+        for filter in list_filters {
+            condition = condition.add(list_filter_condition(fields, filter)?);
+        }
+    }
+
+    // Apply relation filters
+    if let Some(relation_filters) = filters.relation_filters {
+        for filter in relation_filters {
+            condition = condition.add(relation_filter_condition(scope, fields, filter)?);
+        }
+    }
+
+    // Apply backlink filters
+    if let Some(backlink_filters) = filters.backlink_filters {
+        for filter in backlink_filters {
+            condition = condition.add(backlink_filter_condition(scope, filter)?);
+        }
+    }
+
+    // Apply object filters
+    if let Some(object_filters) = filters.object_filters {
+        for filter in object_filters {
+            condition = condition.add(object_filter_condition(fields, filter)?);
+        }
+    }
+
+    // Every nested group must match
+    if let Some(groups) = filters.and {
+        for group in groups {
+            condition = condition.add(compile_filters(scope, fields, group)?);
+        }
+    }
+
+    // At least one nested group must match. Like an empty `and`, an empty
+    // `or` adds no constraint instead of matching nothing.
+    if let Some(groups) = filters.or.filter(|groups| !groups.is_empty()) {
+        let mut any = Condition::any();
+        for group in groups {
+            any = any.add(compile_filters(scope, fields, group)?);
+        }
+        condition = condition.add(any);
+    }
+
+    // The nested group must not match
+    if let Some(group) = filters.not {
+        condition = condition.add(compile_filters(scope, fields, *group)?.not());
+    }
+
+    Ok(condition)
+}
+
+// EXISTS subquery over the value row of `field_id` for the filtered entry
+fn value_exists<T, C>(table: T, entry_column: C, field_column: C, field_id: Uuid, condition: Condition) -> SimpleExpr
+where
+    T: Iden + Copy + 'static,
+    C: Iden + Copy + 'static,
+{
+    Expr::exists(
+        Query::select()
+            .expr(Expr::val(1))
+            .from(table)
+            .cond_where(
+                Condition::all()
+                    .add(Expr::col((table, entry_column)).equals((
+                        entities::entries::Entity,
+                        entities::entries::Column::Id,
+                    )))
+                    .add(Expr::col((table, field_column)).eq(field_id))
+                    .add_option((!condition.is_empty()).then_some(condition)),
+            )
+            .to_owned(),
+    )
+}
+
+// EXISTS subquery over the relation rows of `field_id` for the filtered entry
+fn relation_exists(field_id: Uuid, condition: Condition) -> SimpleExpr {
+    value_exists(
+        entities::entry_relation_values::Entity,
+        entities::entry_relation_values::Column::FromEntryId,
+        entities::entry_relation_values::Column::FieldId,
+        field_id,
+        condition,
+    )
+}
+
+// Match entries whose value row of `field_id` satisfies the comparison, or
+// entries without a value row when there is none (the Missing comparisons)
+fn value_condition<T, C>(table: T, entry_column: C, field_column: C, field_id: Uuid, comparison: Option<SimpleExpr>) -> SimpleExpr
+where
+    T: Iden + Copy + 'static,
+    C: Iden + Copy + 'static,
+{
+    match comparison {
+        Some(comparison) => value_exists(table, entry_column, field_column, field_id, Condition::all().add(comparison)),
+        None => value_exists(table, entry_column, field_column, field_id, Condition::all()).not(),
+    }
+}
+
+// Validate field exists and has correct data type
+pub(crate) fn validate_field<'a>(fields: &'a [Field], field_name: &str, expected_types: &[DataTypes]) -> FieldResult<&'a Field> {
+    let field = fields.iter().find(|f| f.name == field_name)
+        .ok_or_else(|| juniper::FieldError::new(
+            format!("Field '{}' does not exist in collection", field_name),
+            Value::null(),
+        ))?;
+
+    if !expected_types.contains(&field.data_type) {
+        return Err(juniper::FieldError::new(
+            format!("Field '{}' has type {:?}, expected one of {:?}", 
+                field_name, field.data_type, expected_types),
+            Value::null(),
+        ));
+    }
+
+    Ok(field)
+}
+
+// Build text filter condition (includes both Text and TypstText)
+fn text_filter_condition(fields: &[Field], filter: TextFilter) -> FieldResult<SimpleExpr> {
+    let field = validate_field(fields, &filter.field_name, 
+        &[DataTypes::Text, DataTypes::TypstText])?;
+
+    match field.data_type {
+        // TypstText values live in their own table with raw and rendered columns
+        DataTypes::TypstText => {
+            let target = match filter.typst_source.unwrap_or(TypstTextSource::Raw) {
+                TypstTextSource::Raw => Expr::col((
+                    entities::entry_typst_text_values::Entity,
+                    entities::entry_typst_text_values::Column::Raw,
+                ))
+                .into(),
+                TypstTextSource::Rendered => Expr::cust_with_exprs(
+                    typst_plain_text("$1"),
+                    [Expr::col((
+                        entities::entry_typst_text_values::Entity,
+                        entities::entry_typst_text_values::Column::Rendered,
+                    ))
+                    .into()],
+                ),
+            };
+
+            Ok(value_condition(
+                entities::entry_typst_text_values::Entity,
+                entities::entry_typst_text_values::Column::EntryId,
+                entities::entry_typst_text_values::Column::FieldId,
+                field.id,
+                text_comparison(
+                    target,
+                    &filter.comparison,
+                    filter.value.as_ref(),
+                    filter.values.as_ref(),
+                    filter.similarity_threshold,
+                )?,
+            ))
+        }
+        _ => {
+            let target = Expr::col((
+                entities::entry_text_values::Entity,
+                entities::entry_text_values::Column::Value,
+            ))
+            .into();
+
+            Ok(value_condition(
+                entities::entry_text_values::Entity,
+                entities::entry_text_values::Column::EntryId,
+                entities::entry_text_values::Column::FieldId,
+                field.id,
+                text_comparison(
+                    target,
+                    &filter.comparison,
+                    filter.value.as_ref(),
+                    filter.values.as_ref(),
+                    filter.similarity_threshold,
+                )?,
+            ))
+        }
+    }
+}
+
+// Compare a text expression against the inputs of a text filter, None for Missing
+fn text_comparison(
+    target: SimpleExpr,
+    comparison: &TextComparison,
+    value: Option<&String>,
+    values: Option<&Vec<String>>,
+    similarity_threshold: Option<f64>,
+) -> FieldResult<Option<SimpleExpr>> {
+    let target = Expr::expr(target);
+    let values = || required(values.cloned(), "values", comparison);
+    let value = || required(value, "value", comparison);
+    let pattern = |prefix: &str, suffix: &str| -> FieldResult<String> {
+        Ok(format!("{}{}{}", prefix, escape_like(value()?), suffix))
+    };
+    let condition = match comparison {
+        TextComparison::Eq => target.eq(value()?),
+        TextComparison::Neq => target.ne(value()?),
+        TextComparison::Contains => target.like(pattern("%", "%")?),
+        TextComparison::StartsWith => target.like(pattern("", "%")?),
+        TextComparison::EndsWith => target.like(pattern("%", "")?),
+        TextComparison::EqIgnoreCase => target.ilike(pattern("", "")?),
+        TextComparison::ContainsIgnoreCase => target.ilike(pattern("%", "%")?),
+        TextComparison::StartsWithIgnoreCase => target.ilike(pattern("", "%")?),
+        TextComparison::EndsWithIgnoreCase => target.ilike(pattern("%", "")?),
+        TextComparison::Regex => target.binary(PgBinOper::Regex, value()?),
+        TextComparison::RegexIgnoreCase => target.binary(PgBinOper::RegexCaseInsensitive, value()?),
+        TextComparison::Similar => {
+            let threshold = similarity_threshold.unwrap_or(0.3);
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(juniper::FieldError::new(
+                    format!("similarity_threshold must be between 0 and 1, got {}", threshold),
+                    Value::null(),
+                ));
+            }
+            Expr::expr(Expr::cust_with_exprs(
+                "similarity($1, $2)",
+                [target.into(), Expr::val(value()?).into()],
+            ))
+            .gte(threshold)
+        }
+        TextComparison::In => target.is_in(values()?),
+        TextComparison::NotIn => target.is_not_in(values()?),
+        TextComparison::IsNull => target.is_null(),
+        TextComparison::IsNotNull => target.is_not_null(),
+        TextComparison::Missing => return Ok(None),
+    };
+    Ok(Some(condition))
+}
+
+// Escape the LIKE metacharacters of user input so it only matches literally
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Build the condition on the entry row of metadata filters
+fn metadata_condition(scope: &FilterScope, metadata: MetadataFilters) -> FieldResult<Condition> {
+    let mut condition = Condition::all();
+    let column = |c| Expr::col((entities::entries::Entity, c));
+
+    for filter in metadata.name.into_iter().flatten() {
+        let Some(name) = text_comparison(
+            column(entities::entries::Column::Name).into(),
+            &filter.comparison,
+            filter.value.as_ref(),
+            filter.values.as_ref(),
+            filter.similarity_threshold,
+        )?
+        else {
+            return Err(juniper::FieldError::new(
+                "Every entry has a name, so Missing does not apply to name filters".to_string(),
+                Value::null(),
+            ));
+        };
+        condition = condition.add(name);
+    }
+
+    if let Some(users) = metadata.created_by {
+        condition = condition.add(column(entities::entries::Column::CreatedBy).is_in(users));
+    }
+    if metadata.created_by_me == Some(true) {
+        let user = scope.user.ok_or_else(|| juniper::FieldError::new(
+            "Authentication required",
+            juniper::graphql_value!({ "code": "UNAUTHENTICATED" }),
+        ))?;
+        condition = condition.add(column(entities::entries::Column::CreatedBy).eq(user));
+    }
+
+    if metadata.created_after.is_some() || metadata.relative_created_after.is_some() {
+        let after = resolve_datetime(metadata.created_after, metadata.relative_created_after.as_deref(), "created_after")?;
+        condition = condition.add(column(entities::entries::Column::CreatedAt).gte(after));
+    }
+    if metadata.created_before.is_some() || metadata.relative_created_before.is_some() {
+        let before = resolve_datetime(metadata.created_before, metadata.relative_created_before.as_deref(), "created_before")?;
+        condition = condition.add(column(entities::entries::Column::CreatedAt).lte(before));
+    }
+
+    Ok(condition)
+}
+
+// Build number filter condition
+fn number_filter_condition(fields: &[Field], filter: NumberFilter) -> FieldResult<SimpleExpr> {
+    let field = validate_field(fields, &filter.field_name, &[DataTypes::Number])?;
+
+    let column = entities::entry_number_values::Column::Value;
+    let value = || required(filter.value, "value", &filter.comparison);
+    let condition = match filter.comparison {
+        NumberComparison::Eq => Some(column.eq(value()?)),
+        NumberComparison::Neq => Some(column.ne(value()?)),
+        NumberComparison::Gt => Some(column.gt(value()?)),
+        NumberComparison::Gte => Some(column.gte(value()?)),
+        NumberComparison::Lt => Some(column.lt(value()?)),
+        NumberComparison::Lte => Some(column.lte(value()?)),
+        NumberComparison::Between => {
+            let (start, end) = (value()?, required(filter.end_value, "end_value", &filter.comparison)?);
+            if end < start {
+                return Err(juniper::FieldError::new(
+                    format!("Between range for field '{}' ends before it starts", filter.field_name),
+                    Value::null(),
+                ));
+            }
+            Some(column.between(start, end))
+        }
+        NumberComparison::In => Some(column.is_in(required(filter.values.clone(), "values", &filter.comparison)?)),
+        NumberComparison::NotIn => Some(column.is_not_in(required(filter.values.clone(), "values", &filter.comparison)?)),
+        NumberComparison::IsNull => Some(column.is_null()),
+        NumberComparison::IsNotNull => Some(column.is_not_null()),
+        NumberComparison::Missing => None,
+    };
+
+    Ok(value_condition(
+        entities::entry_number_values::Entity,
+        entities::entry_number_values::Column::EntryId,
+        entities::entry_number_values::Column::FieldId,
+        field.id,
+        condition,
+    ))
+}
+
+// Build boolean filter condition
+fn boolean_filter_condition(fields: &[Field], filter: BooleanFilter) -> FieldResult<SimpleExpr> {
+    let field = validate_field(fields, &filter.field_name, &[DataTypes::Boolean])?;
+
+    let column = entities::entry_boolean_values::Column::Value;
+    let value = || required(filter.value, "value", &filter.comparison);
+    let condition = match filter.comparison {
+        BooleanComparison::Eq => Some(column.eq(value()?)),
+        BooleanComparison::Neq => Some(column.ne(value()?)),
+        BooleanComparison::IsNull => Some(column.is_null()),
+        BooleanComparison::IsNotNull => Some(column.is_not_null()),
+        BooleanComparison::Missing => None,
+    };
+
+    Ok(value_condition(
+        entities::entry_boolean_values::Entity,
+        entities::entry_boolean_values::Column::EntryId,
+        entities::entry_boolean_values::Column::FieldId,
+        field.id,
+        condition,
+    ))
+}
+
+// Build datetime filter condition
+fn datetime_filter_condition(fields: &[Field], filter: DateTimeFilter) -> FieldResult<SimpleExpr> {
+    let field = validate_field(fields, &filter.field_name, &[DataTypes::DateTime])?;
+
+    let column = entities::entry_date_time_values::Column::Value;
+    let value = || resolve_datetime(filter.value, filter.relative_value.as_deref(), "value");
+    let condition = match filter.comparison {
+        DateTimeComparison::Eq => Some(column.eq(value()?)),
+        DateTimeComparison::Neq => Some(column.ne(value()?)),
+        DateTimeComparison::Gt => Some(column.gt(value()?)),
+        DateTimeComparison::Gte => Some(column.gte(value()?)),
+        DateTimeComparison::Lt => Some(column.lt(value()?)),
+        DateTimeComparison::Lte => Some(column.lte(value()?)),
+        DateTimeComparison::Between => {
+            let start = value()?;
+            let end = resolve_datetime(filter.end_value, filter.relative_end_value.as_deref(), "end_value")?;
+            if end < start {
+                return Err(juniper::FieldError::new(
+                    format!("Between range for field '{}' ends before it starts", filter.field_name),
+                    Value::null(),
+                ));
+            }
+            Some(column.between(start, end))
+        }
+        DateTimeComparison::IsNull => Some(column.is_null()),
+        DateTimeComparison::IsNotNull => Some(column.is_not_null()),
+        DateTimeComparison::Missing => None,
+    };
+
+    Ok(value_condition(
+        entities::entry_date_time_values::Entity,
+        entities::entry_date_time_values::Column::EntryId,
+        entities::entry_date_time_values::Column::FieldId,
+        field.id,
+        condition,
+    ))
+}
+
+// Unwrap an input the comparison of a filter cannot do without
+fn required<T, C: std::fmt::Debug>(value: Option<T>, name: &str, comparison: &C) -> FieldResult<T> {
+    value.ok_or_else(|| juniper::FieldError::new(
+        format!("{} is required for the {:?} comparison", name, comparison),
+        Value::null(),
+    ))
+}
+
+// Resolve a datetime bound given either as a timestamp or as a relative expression.
+// Values are stored as UTC timestamps without a time zone.
+fn resolve_datetime(absolute: Option<DateTime<Utc>>, relative: Option<&str>, name: &str) -> FieldResult<NaiveDateTime> {
+    let value = match (absolute, relative) {
+        (Some(value), None) => value,
+        (None, Some(expression)) => relative_time::resolve(expression, Utc::now())
+            .map_err(|message| juniper::FieldError::new(message, Value::null()))?,
+        (Some(_), Some(_)) => {
+            return Err(juniper::FieldError::new(
+                format!("Only one of {} and relative_{} may be given", name, name),
+                Value::null(),
+            ));
+        }
+        (None, None) => {
+            return Err(juniper::FieldError::new(
+                format!("Either {} or relative_{} is required", name, name),
+                Value::null(),
+            ));
+        }
+    };
+    Ok(value.naive_utc())
+}
+
+// Build list filter condition
+fn list_filter_condition(fields: &[Field], filter: ListFilter) -> FieldResult<SimpleExpr> {
+    let field = validate_field(fields, &filter.field_name, 
+        &[DataTypes::TextList, DataTypes::NumberList])?;
+
+    match field.data_type {
+        DataTypes::TextList => {
+            if matches!(filter.comparison, ListComparison::AnyGt | ListComparison::AnyGte
+                | ListComparison::AnyLt | ListComparison::AnyLte)
+            {
+                return Err(juniper::FieldError::new(
+                    format!("{:?} is only supported for NumberList fields", filter.comparison),
+                    Value::null(),
+                ));
+            }
+            list_condition(
+                entities::entry_text_list_values::Entity,
+                entities::entry_text_list_values::Column::EntryId,
+                entities::entry_text_list_values::Column::FieldId,
+                entities::entry_text_list_values::Column::Value,
+                field.id,
+                &filter,
+                filter.values.clone().map(sea_orm::Value::from),
+            )
+        }
+        DataTypes::NumberList => list_condition(
+            entities::entry_number_list_values::Entity,
+            entities::entry_number_list_values::Column::EntryId,
+            entities::entry_number_list_values::Column::FieldId,
+            entities::entry_number_list_values::Column::Value,
+            field.id,
+            &filter,
+            filter.number_values.clone().map(sea_orm::Value::from),
+        ),
+        _ => unreachable!(), // validate_field ensures correct types
+    }
+}
+
+// Build a list condition using Postgres array operators on either list table
+fn list_condition<T, C>(
+    table: T,
+    entry_column: C,
+    field_column: C,
+    value_column: C,
+    field_id: Uuid,
+    filter: &ListFilter,
+    values: Option<sea_orm::Value>,
+) -> FieldResult<SimpleExpr>
+where
+    T: Iden + Copy + 'static,
+    C: Iden + Copy + 'static,
+{
+    let exists = |condition: SimpleExpr| {
+        value_exists(table, entry_column, field_column, field_id, Condition::all().add(condition))
+    };
+    let column = || Expr::col((table, value_column));
+    let length = || Expr::cust_with_exprs("coalesce(cardinality($1), 0)", [column().into()]);
+    let required_values = || values.clone().ok_or_else(|| juniper::FieldError::new(
+        format!("values are required for the {:?} comparison on field '{}'", filter.comparison, filter.field_name),
+        Value::null(),
+    ));
+
+    let condition = match filter.comparison {
+        // Contains checks for the given value(s), same as ContainsAll
+        ListComparison::Contains | ListComparison::ContainsAll => {
+            exists(column().binary(PgBinOper::Contains, required_values()?))
+        }
+        ListComparison::ContainsAny => exists(column().binary(PgBinOper::Overlap, required_values()?)),
+        // A missing row, a NULL array and an empty array are all empty lists
+        ListComparison::IsEmpty => exists(Expr::expr(length()).gt(0)).not(),
+        ListComparison::IsNotEmpty => exists(Expr::expr(length()).gt(0)),
+        ListComparison::LengthEq
+        | ListComparison::LengthGt
+        | ListComparison::LengthGte
+        | ListComparison::LengthLt
+        | ListComparison::LengthLte => {
+            let Some(expected) = filter.length else {
+                return Err(juniper::FieldError::new(
+                    format!("length is required for the {:?} comparison", filter.comparison),
+                    Value::null(),
+                ));
+            };
+            let (operator, matches_empty) = match filter.comparison {
+                ListComparison::LengthEq => (BinOper::Equal, 0 == expected),
+                ListComparison::LengthGt => (BinOper::GreaterThan, 0 > expected),
+                ListComparison::LengthGte => (BinOper::GreaterThanOrEqual, 0 >= expected),
+                ListComparison::LengthLt => (BinOper::SmallerThan, 0 < expected),
+                _ => (BinOper::SmallerThanOrEqual, 0 <= expected),
+            };
+            let stored = exists(length().binary(operator, expected));
+            // Entries without a row count as length 0
+            if matches_empty {
+                stored.or(value_exists(table, entry_column, field_column, field_id, Condition::all()).not())
+            } else {
+                stored
+            }
+        }
+        ListComparison::AnyGt
+        | ListComparison::AnyGte
+        | ListComparison::AnyLt
+        | ListComparison::AnyLte => {
+            let Some(bound) = filter.number_value else {
+                return Err(juniper::FieldError::new(
+                    format!("number_value is required for the {:?} comparison", filter.comparison),
+                    Value::null(),
+                ));
+            };
+            // "some element > bound" is written as "bound < ANY(list)"
+            let operator = match filter.comparison {
+                ListComparison::AnyGt => "<",
+                ListComparison::AnyGte => "<=",
+                ListComparison::AnyLt => ">",
+                _ => ">=",
+            };
+            exists(Expr::cust_with_exprs(
+                format!("$1 {} ANY($2)", operator),
+                [Expr::val(bound).into(), column().into()],
+            ))
+        }
+    };
+
+    Ok(condition)
+}
+
+// Build relation filter condition
+fn relation_filter_condition(scope: &FilterScope, fields: &[Field], filter: RelationFilter) -> FieldResult<SimpleExpr> {
+    let field = validate_field(fields, &filter.field_name, &[DataTypes::Relation])?;
+
+    // Restrict the relation rows to targets matching the nested filters
+    let mut targets = Condition::all();
+    if let Some(related) = filter.target_matches {
+        let (collection_id, related_fields) = scope.collection(&related.collection)?;
+        let target_condition = Condition::all()
+            .add(Expr::col((entities::entries::Entity, entities::entries::Column::CollectionId)).eq(*collection_id))
+            .add(compile_filters(scope, related_fields, related.filters)?);
+        targets = targets.add(entities::entry_relation_values::Column::ToEntryId.in_subquery(entry_ids(target_condition)));
+    }
+
+    // Each target as a condition on the relation row
+    let mut target_rows: Vec<SimpleExpr> = filter
+        .target_entry_id
+        .into_iter()
+        .chain(filter.target_entry_ids.into_iter().flatten())
+        .map(|id| entities::entry_relation_values::Column::ToEntryId.eq(id))
+        .collect();
+    for target in filter.target_entries.into_iter().flatten() {
+        target_rows.push(entities::entry_relation_values::Column::ToEntryId.in_subquery(entry_reference_ids(target)));
+    }
+
+    let condition = match filter.comparison {
+        RelationComparison::ConnectedTo
+        | RelationComparison::NotConnectedTo
+        | RelationComparison::ConnectedToAny
+        | RelationComparison::ConnectedToAll => {
+            if target_rows.is_empty() {
+                return Err(juniper::FieldError::new(
+                    format!(
+                        "target_entry_id, target_entry_ids or target_entries is required for the {:?} comparison",
+                        filter.comparison
+                    ),
+                    Value::null(),
+                ));
+            }
+
+            // The listed targets only count when they also match the nested filters
+            match filter.comparison {
+                // Every target needs its own relation row
+                RelationComparison::ConnectedToAll => target_rows
+                    .into_iter()
+                    .map(|target| relation_exists(field.id, targets.clone().add(target)))
+                    .reduce(SimpleExpr::and)
+                    .expect("targets are not empty"),
+                _ => {
+                    // Check whether the entry has a relation to any of the targets
+                    let connected = relation_exists(
+                        field.id,
+                        targets.add(target_rows.into_iter().fold(Condition::any(), Condition::add)),
+                    );
+                    match filter.comparison {
+                        RelationComparison::NotConnectedTo => connected.not(),
+                        _ => connected,
+                    }
+                }
+            }
+        }
+        // Check whether the entry has any relations for this field
+        RelationComparison::HasConnections => relation_exists(field.id, targets),
+        RelationComparison::HasNoConnections => relation_exists(field.id, targets).not(),
+    };
+
+    Ok(condition)
+}
+
+// Id of the entry named by a reference, empty when there is none
+fn entry_reference_ids(reference: EntryReference) -> SelectStatement {
+    entry_ids(
+        Condition::all()
+            .add(entities::entries::Column::Name.eq(reference.name))
+            .add(
+                entities::entries::Column::CollectionId.in_subquery(
+                    Query::select()
+                        .column(entities::collections::Column::Id)
+                        .from(entities::collections::Entity)
+                        .and_where(entities::collections::Column::Name.eq(reference.collection))
+                        .to_owned(),
+                ),
+            ),
+    )
+}
+
+// Build backlink filter condition over relations pointing at the entry
+fn backlink_filter_condition(scope: &FilterScope, filter: BacklinkFilter) -> FieldResult<SimpleExpr> {
+    let mut links = Condition::all().add(
+        Expr::col((
+            entities::entry_relation_values::Entity,
+            entities::entry_relation_values::Column::ToEntryId,
+        ))
+        .equals((entities::entries::Entity, entities::entries::Column::Id)),
+    );
+
+    match &filter.collection {
+        Some(collection) => {
+            let (collection_id, source_fields) = scope.collection(collection)?;
+            if let Some(field_name) = &filter.field_name {
+                let field = validate_field(source_fields, field_name, &[DataTypes::Relation])?;
+                links = links.add(entities::entry_relation_values::Column::FieldId.eq(field.id));
+            }
+
+            let mut sources = Condition::all().add(
+                Expr::col((entities::entries::Entity, entities::entries::Column::CollectionId)).eq(*collection_id),
+            );
+            if let Some(source_filters) = filter.source_matches {
+                sources = sources.add(compile_filters(scope, source_fields, source_filters)?);
+            }
+            links = links.add(entities::entry_relation_values::Column::FromEntryId.in_subquery(entry_ids(sources)));
+        }
+        None if filter.field_name.is_some() || filter.source_matches.is_some() => {
+            return Err(juniper::FieldError::new(
+                "collection is required to filter backlinks by field_name or source_matches".to_string(),
+                Value::null(),
+            ));
+        }
+        None => {}
+    }
+
+    let linked = Expr::exists(
+        Query::select()
+            .expr(Expr::val(1))
+            .from(entities::entry_relation_values::Entity)
+            .cond_where(links)
+            .to_owned(),
+    );
+
+    Ok(match filter.comparison {
+        BacklinkComparison::HasBacklinks => linked,
+        BacklinkComparison::HasNoBacklinks => linked.not(),
+    })
+}
+
+// Build object filter condition
+fn object_filter_condition(fields: &[Field], filter: ObjectFilter) -> FieldResult<SimpleExpr> {
+    let field = validate_field(fields, &filter.field_name, &[DataTypes::Object])?;
+
+    let condition = match filter.comparison {
+        ObjectComparison::HasProperty => {
+            let segments = property_path_segments(&filter)?;
+            // A property holding JSON null still exists, so only SQL NULL means missing
+            Expr::expr(json_property(&segments, "#>")).is_not_null()
+        }
+        ObjectComparison::PropertyEquals => {
+            let segments = property_path_segments(&filter)?;
+            let (_, value) = typed_property_value(&filter)?;
+            Expr::expr(json_property(&segments, "#>")).eq(value)
+        }
+        ObjectComparison::PropertyContains => {
+            let segments = property_path_segments(&filter)?;
+            let (raw, value) = typed_property_value(&filter)?;
+            // Arrays contain the value as an element, objects and scalars by JSONB containment
+            let contains = json_property(&segments, "#>").binary(PgBinOper::Contains, Expr::val(value));
+            match filter.property_type.unwrap_or(PropertyValueType::String) {
+                // Strings additionally match on substring
+                PropertyValueType::String => contains.or(
+                    Expr::expr(json_property(&segments, "#>>")).like(format!("%{}%", escape_like(&raw))),
+                ),
+                _ => contains,
+            }
+        }
+        ObjectComparison::IsEmpty => {
+            // Check if the JSON object is null (basic check)
+            entities::entry_object_values::Column::Value.is_null()
+        }
+        ObjectComparison::IsNotEmpty => {
+            // Check if the JSON object is not null (basic check)
+            entities::entry_object_values::Column::Value.is_not_null()
+        }
+    };
+
+    Ok(value_exists(
+        entities::entry_object_values::Entity,
+        entities::entry_object_values::Column::EntryId,
+        entities::entry_object_values::Column::FieldId,
+        field.id,
+        Condition::all().add(condition),
+    ))
+}
+
+// Extract a property of the object value, `#>` as JSONB or `#>>` as text
+fn json_property(segments: &[String], operator: &str) -> SimpleExpr {
+    Expr::cust_with_exprs(
+        format!("$1 {} $2", operator),
+        [
+            Expr::col((
+                entities::entry_object_values::Entity,
+                entities::entry_object_values::Column::Value,
+            ))
+            .into(),
+            Expr::val(segments.to_vec()).into(),
+        ],
+    )
+}
+
+// Resolve the required property_path of an object filter
+fn property_path_segments(filter: &ObjectFilter) -> FieldResult<Vec<String>> {
+    let path = filter.property_path.as_deref().ok_or_else(|| juniper::FieldError::new(
+        "property_path is required for HasProperty, PropertyEquals and PropertyContains comparisons".to_string(),
+        Value::null(),
+    ))?;
+    parse_property_path(path)
+}
+
+// Split a path like "links[0].url" into JSONB path segments ["links", "0", "url"]
+fn parse_property_path(path: &str) -> FieldResult<Vec<String>> {
+    let invalid = || juniper::FieldError::new(
+        format!("Invalid property path '{}'. Use dotted keys with optional array indexes, e.g. 'links[0].url'", path),
+        Value::null(),
+    );
+
+    let mut segments = Vec::new();
+    for part in path.split('.') {
+        let (key, mut rest) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if !key.is_empty() {
+            segments.push(key.to_string());
+        } else if rest.is_empty() || !segments.is_empty() {
+            // Only a leading index may omit its key, as in "[0].url"
+            return Err(invalid());
+        }
+
+        while let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(invalid)?;
+            let index: usize = after[..end].parse().map_err(|_| invalid())?;
+            segments.push(index.to_string());
+            rest = &after[end + 1..];
+        }
+        if !rest.is_empty() {
+            return Err(invalid());
+        }
+    }
+
+    Ok(segments)
+}
+
+// Parse the required property_value of an object filter into a typed JSON value
+fn typed_property_value(filter: &ObjectFilter) -> FieldResult<(String, serde_json::Value)> {
+    let raw = filter.property_value.clone().ok_or_else(|| juniper::FieldError::new(
+        "property_value is required for PropertyEquals and PropertyContains comparisons".to_string(),
+        Value::null(),
+    ))?;
+
+    let value = match filter.property_type.unwrap_or(PropertyValueType::String) {
+        PropertyValueType::String => serde_json::Value::String(raw.clone()),
+        PropertyValueType::Number => raw
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(serde_json::Value::Number)
+            .ok_or_else(|| juniper::FieldError::new(
+                format!("Property value '{}' is not a valid number", raw),
+                Value::null(),
+            ))?,
+        PropertyValueType::Boolean => raw
+            .trim()
+            .parse::<bool>()
+            .map(serde_json::Value::Bool)
+            .map_err(|_| juniper::FieldError::new(
+                format!("Property value '{}' is not a valid boolean, expected 'true' or 'false'", raw),
+                Value::null(),
+            ))?,
+    };
+
+    Ok((raw, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::sea_query::PostgresQueryBuilder;
+
+    fn field(name: &str, data_type: DataTypes) -> Field {
+        Field {
+            id: Uuid::new_v4(),
+            collection_id: Uuid::nil(),
+            name: name.to_string(),
+            data_type,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn no_filters() -> EntryFilters {
+        EntryFilters {
+            metadata: None,
+            text_filters: None,
+            number_filters: None,
+            boolean_filters: None,
+            date_time_filters: None,
+            list_filters: None,
+            relation_filters: None,
+            backlink_filters: None,
+            object_filters: None,
+            and: None,
+            or: None,
+            not: None,
+        }
+    }
+
+    fn text_filter(field_name: &str, comparison: TextComparison, value: &str) -> TextFilter {
+        TextFilter {
+            field_name: field_name.to_string(),
+            comparison,
+            value: Some(value.to_string()),
+            values: None,
+            typst_source: None,
+            similarity_threshold: None,
+        }
+    }
+
+    fn sql(condition: Condition) -> String {
+        entry_ids(condition).to_string(PostgresQueryBuilder)
+    }
+
+    fn segments(path: &str) -> Vec<String> {
+        parse_property_path(path).unwrap_or_else(|_| panic!("'{}' should parse", path))
+    }
+
+    #[test]
+    fn property_path_dotted_keys() {
+        assert_eq!(segments("address.city"), ["address", "city"]);
+        assert_eq!(segments("name"), ["name"]);
+    }
+
+    #[test]
+    fn property_path_array_indexes() {
+        assert_eq!(segments("links[0].url"), ["links", "0", "url"]);
+        assert_eq!(segments("matrix[1][2]"), ["matrix", "1", "2"]);
+    }
+
+    #[test]
+    fn property_path_leading_index() {
+        assert_eq!(segments("[0].url"), ["0", "url"]);
+    }
+
+    #[test]
+    fn property_path_rejects_malformed_paths() {
+        for path in ["a..b", "a[x]", "", "a.", ".a", "a[0", "a[-1]", "a[0]b", "a.[0]"] {
+            assert!(parse_property_path(path).is_err(), "'{}' should be rejected", path);
+        }
+    }
+
+    #[test]
+    fn relation_targets_must_match_nested_filters() {
+        let authors = Uuid::new_v4();
+        let scope = FilterScope {
+            collections: HashMap::from([("authors".to_string(), (authors, vec![field("name", DataTypes::Text)]))]),
+            user: None,
+        };
+        let fields = [field("author", DataTypes::Relation)];
+        let target = Uuid::new_v4();
+        let filters = EntryFilters {
+            relation_filters: Some(vec![RelationFilter {
+                field_name: "author".to_string(),
+                comparison: RelationComparison::ConnectedTo,
+                target_entry_id: Some(target),
+                target_entry_ids: None,
+                target_entries: None,
+                target_matches: Some(RelatedEntryFilters {
+                    collection: "authors".to_string(),
+                    filters: EntryFilters {
+                        text_filters: Some(vec![text_filter("name", TextComparison::Eq, "Ada")]),
+                        ..no_filters()
+                    },
+                }),
+            }]),
+            ..no_filters()
+        };
+
+        let sql = sql(compile_filters(&scope, &fields, filters).unwrap());
+        // One relation row has to be both a listed target and a matching entry
+        assert_eq!(sql.matches(r#"FROM "entry_relation_values""#).count(), 1);
+        assert!(sql.contains(&format!(r#""entry_relation_values"."to_entry_id" = '{}'"#, target)));
+        assert!(sql.contains(&format!(r#""entries"."collection_id" = '{}'"#, authors)));
+        assert!(sql.contains(r#""to_entry_id" IN (SELECT "entries"."id" FROM "entries""#));
+    }
+
+    // The subquery an Eq text filter compiles to
+    fn text_exists(field: &Field, value: &str) -> String {
+        format!(
+            r#"EXISTS(SELECT 1 FROM "entry_text_values" WHERE "entry_text_values"."entry_id" = "entries"."id" AND "entry_text_values"."field_id" = '{}' AND "entry_text_values"."value" = '{}')"#,
+            field.id, value
+        )
+    }
+
+    fn text_sql(comparison: TextComparison, value: &str) -> String {
+        let target = Expr::col(entities::entries::Column::Name).into();
+        let condition = text_comparison(target, &comparison, Some(&value.to_string()), None, None).unwrap().unwrap();
+        sql(Condition::all().add(condition))
+    }
+
+    #[test]
+    fn like_metacharacters_are_escaped() {
+        assert_eq!(escape_like("50%_off\\"), "50\\%\\_off\\\\");
+        assert_eq!(escape_like("plain text"), "plain text");
+    }
+
+    #[test]
+    fn text_comparisons_match_user_input_literally() {
+        // Postgres E'' strings double the backslashes of the LIKE escapes
+        let select = r#"SELECT "entries"."id" FROM "entries" WHERE"#;
+        assert_eq!(
+            text_sql(TextComparison::Contains, "50%_off\\"),
+            format!(r#"{} "name" LIKE E'%50\\%\\_off\\\\%'"#, select)
+        );
+        assert_eq!(
+            text_sql(TextComparison::EqIgnoreCase, "50%_off\\"),
+            format!(r#"{} "name" ILIKE E'50\\%\\_off\\\\'"#, select)
+        );
+        assert_eq!(
+            text_sql(TextComparison::StartsWithIgnoreCase, "50%_off\\"),
+            format!(r#"{} "name" ILIKE E'50\\%\\_off\\\\%'"#, select)
+        );
+    }
+
+    #[test]
+    fn text_filters_on_different_fields_get_their_own_subquery() {
+        let scope = FilterScope { collections: HashMap::new(), user: None };
+        let fields = [field("title", DataTypes::Text), field("body", DataTypes::Text)];
+        let filters = EntryFilters {
+            text_filters: Some(vec![
+                text_filter("title", TextComparison::Eq, "Draft"),
+                text_filter("body", TextComparison::Eq, "Lorem"),
+            ]),
+            ..no_filters()
+        };
+
+        assert_eq!(
+            sql(compile_filters(&scope, &fields, filters).unwrap()),
+            format!(
+                r#"SELECT "entries"."id" FROM "entries" WHERE {} AND {}"#,
+                text_exists(&fields[0], "Draft"),
+                text_exists(&fields[1], "Lorem")
+            )
+        );
+    }
+
+    #[test]
+    fn filter_groups_nest() {
+        let scope = FilterScope { collections: HashMap::new(), user: None };
+        let fields = [field("a", DataTypes::Text), field("b", DataTypes::Text), field("c", DataTypes::Text)];
+        let eq = |i: usize, value: &str| EntryFilters {
+            text_filters: Some(vec![text_filter(&fields[i].name, TextComparison::Eq, value)]),
+            ..no_filters()
+        };
+        // a = x AND (b = y OR NOT c = z)
+        let filters = EntryFilters {
+            and: Some(vec![
+                eq(0, "x"),
+                EntryFilters {
+                    or: Some(vec![eq(1, "y"), EntryFilters { not: Some(Box::new(eq(2, "z"))), ..no_filters() }]),
+                    ..no_filters()
+                },
+            ]),
+            ..no_filters()
+        };
+
+        assert_eq!(
+            sql(compile_filters(&scope, &fields, filters).unwrap()),
+            format!(
+                r#"SELECT "entries"."id" FROM "entries" WHERE {} AND ({} OR (NOT {}))"#,
+                text_exists(&fields[0], "x"),
+                text_exists(&fields[1], "y"),
+                text_exists(&fields[2], "z")
+            )
+        );
+    }
+
+    #[test]
+    fn empty_filter_groups_add_no_constraint() {
+        let scope = FilterScope { collections: HashMap::new(), user: None };
+        let fields = [field("a", DataTypes::Text)];
+        let expected = format!(r#"SELECT "entries"."id" FROM "entries" WHERE {}"#, text_exists(&fields[0], "x"));
+        for (and, or) in [(Some(vec![]), None), (None, Some(vec![])), (Some(vec![]), Some(vec![]))] {
+            let filters = EntryFilters {
+                text_filters: Some(vec![text_filter("a", TextComparison::Eq, "x")]),
+                and,
+                or,
+                ..no_filters()
+            };
+            assert_eq!(sql(compile_filters(&scope, &fields, filters).unwrap()), expected);
+        }
+    }
+}
@@ -1,2 +1,8 @@
+pub mod aggregates;
+pub mod backlinks;
 pub mod collection;
-pub mod entries;
\ No newline at end of file
+pub mod connection;
+pub mod entries;
+pub mod filters;
+pub mod ordering;
+pub mod search;
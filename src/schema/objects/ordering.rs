@@ -0,0 +1,239 @@
+use entities::sea_orm_active_enums::DataTypes;
+use juniper::{FieldResult, GraphQLEnum, GraphQLInputObject, Value};
+use sea_orm::{
+    Condition, QueryOrder,
+    sea_query::{Expr, Iden, NullOrdering, Order, Query, SimpleExpr},
+};
+use uuid::Uuid;
+
+use super::collection::Field;
+use super::filters::validate_field;
+
+#[derive(GraphQLEnum, Clone, Copy)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(GraphQLEnum, Clone, Copy)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+// Built-in entry columns that can be sorted on
+#[derive(GraphQLEnum, Clone, Copy)]
+pub enum EntrySortKey {
+    Name,
+    CreatedAt,
+}
+
+#[derive(GraphQLInputObject, Clone)]
+pub struct EntrySort {
+    pub field: Option<String>, // Text, TypstText, Number, Boolean or DateTime field to sort on
+    pub key: Option<EntrySortKey>, // Built-in column to sort on, instead of field
+    pub direction: Option<SortDirection>, // Defaults to Asc
+    pub nulls: Option<NullsOrder>, // Where entries without a value go, defaults to Last
+}
+
+// One key of an ordering. `cast` is the SQL type of the key, used to read its
+// value back from a cursor, and `name` identifies the key within a cursor.
+#[derive(Clone)]
+pub struct SortKey {
+    pub name: String,
+    pub expr: SimpleExpr,
+    pub order: Order,
+    pub nulls: NullOrdering,
+    pub cast: &'static str,
+}
+
+impl SortKey {
+    pub fn asc(name: &str, expr: SimpleExpr, cast: &'static str) -> Self {
+        SortKey {
+            name: name.to_string(),
+            expr,
+            order: Order::Asc,
+            nulls: NullOrdering::Last,
+            cast,
+        }
+    }
+
+    // The same key walked in the opposite direction
+    pub fn reversed(&self) -> Self {
+        SortKey {
+            order: match self.order {
+                Order::Desc => Order::Asc,
+                _ => Order::Desc,
+            },
+            nulls: match self.nulls {
+                NullOrdering::First => NullOrdering::Last,
+                NullOrdering::Last => NullOrdering::First,
+            },
+            ..self.clone()
+        }
+    }
+}
+
+// Order any query by the given keys in turn
+pub fn order_by_keys<Q: QueryOrder>(mut query: Q, keys: &[SortKey]) -> Q {
+    for key in keys {
+        query = query.order_by_with_nulls(key.expr.clone(), key.order.clone(), key.nulls);
+    }
+    query
+}
+
+// Resolve the sort keys of an entries query, ending with the id so that the
+// order is stable. Without sort keys entries are ordered by creation date.
+pub fn entry_sort_keys(fields: &[Field], sorts: Option<Vec<EntrySort>>) -> FieldResult<Vec<SortKey>> {
+    let sorts = sorts.unwrap_or_else(|| {
+        vec![EntrySort {
+            field: None,
+            key: Some(EntrySortKey::CreatedAt),
+            direction: None,
+            nulls: None,
+        }]
+    });
+
+    let mut keys = Vec::with_capacity(sorts.len() + 1);
+    for sort in sorts {
+        let (name, expr, cast) = sort_expr(fields, &sort)?;
+        keys.push(SortKey {
+            name,
+            expr,
+            order: match sort.direction.unwrap_or(SortDirection::Asc) {
+                SortDirection::Asc => Order::Asc,
+                SortDirection::Desc => Order::Desc,
+            },
+            nulls: match sort.nulls.unwrap_or(NullsOrder::Last) {
+                NullsOrder::First => NullOrdering::First,
+                NullsOrder::Last => NullOrdering::Last,
+            },
+            cast,
+        });
+    }
+    keys.push(SortKey::asc(
+        "id",
+        Expr::col((entities::entries::Entity, entities::entries::Column::Id)).into(),
+        "uuid",
+    ));
+
+    Ok(keys)
+}
+
+// Order an entries query by the given sort keys
+pub fn apply_sorts(
+    query: sea_orm::Select<entities::entries::Entity>,
+    fields: &[Field],
+    sorts: Option<Vec<EntrySort>>,
+) -> FieldResult<sea_orm::Select<entities::entries::Entity>> {
+    Ok(order_by_keys(query, &entry_sort_keys(fields, sorts)?))
+}
+
+// Resolve the name, expression and SQL type of a sort key
+fn sort_expr(fields: &[Field], sort: &EntrySort) -> FieldResult<(String, SimpleExpr, &'static str)> {
+    let name = match (&sort.field, sort.key) {
+        (Some(name), None) => name,
+        (None, Some(key)) => {
+            let (name, column, cast) = match key {
+                EntrySortKey::Name => ("key:name", entities::entries::Column::Name, "text"),
+                EntrySortKey::CreatedAt => ("key:createdAt", entities::entries::Column::CreatedAt, "timestamp"),
+            };
+            return Ok((name.to_string(), Expr::col((entities::entries::Entity, column)).into(), cast));
+        }
+        (Some(_), Some(_)) => {
+            return Err(juniper::FieldError::new(
+                "Only one of field and key may be given in a sort".to_string(),
+                Value::null(),
+            ));
+        }
+        (None, None) => {
+            return Err(juniper::FieldError::new(
+                "Either field or key is required in a sort".to_string(),
+                Value::null(),
+            ));
+        }
+    };
+
+    let field = validate_field(fields, name, &[
+        DataTypes::Text,
+        DataTypes::TypstText,
+        DataTypes::Number,
+        DataTypes::Boolean,
+        DataTypes::DateTime,
+    ])?;
+
+    let cast = match field.data_type {
+        DataTypes::Number => "double precision",
+        DataTypes::Boolean => "boolean",
+        DataTypes::DateTime => "timestamp",
+        _ => "text",
+    };
+    let expr = match field.data_type {
+        DataTypes::Text => value_subquery(
+            entities::entry_text_values::Entity,
+            entities::entry_text_values::Column::EntryId,
+            entities::entry_text_values::Column::FieldId,
+            entities::entry_text_values::Column::Value,
+            field.id,
+        ),
+        DataTypes::TypstText => value_subquery(
+            entities::entry_typst_text_values::Entity,
+            entities::entry_typst_text_values::Column::EntryId,
+            entities::entry_typst_text_values::Column::FieldId,
+            entities::entry_typst_text_values::Column::Raw,
+            field.id,
+        ),
+        DataTypes::Number => value_subquery(
+            entities::entry_number_values::Entity,
+            entities::entry_number_values::Column::EntryId,
+            entities::entry_number_values::Column::FieldId,
+            entities::entry_number_values::Column::Value,
+            field.id,
+        ),
+        DataTypes::Boolean => value_subquery(
+            entities::entry_boolean_values::Entity,
+            entities::entry_boolean_values::Column::EntryId,
+            entities::entry_boolean_values::Column::FieldId,
+            entities::entry_boolean_values::Column::Value,
+            field.id,
+        ),
+        DataTypes::DateTime => value_subquery(
+            entities::entry_date_time_values::Entity,
+            entities::entry_date_time_values::Column::EntryId,
+            entities::entry_date_time_values::Column::FieldId,
+            entities::entry_date_time_values::Column::Value,
+            field.id,
+        ),
+        _ => unreachable!(), // validate_field ensures correct types
+    };
+
+    Ok((format!("field:{}", field.name), expr, cast))
+}
+
+// Scalar subquery reading the value of `field_id` for the sorted entry, typed
+// by its column so values compare as text, numbers, booleans or timestamps
+fn value_subquery<T, C>(table: T, entry_column: C, field_column: C, value_column: C, field_id: Uuid) -> SimpleExpr
+where
+    T: Iden + Copy + 'static,
+    C: Iden + Copy + 'static,
+{
+    SimpleExpr::SubQuery(
+        None,
+        Box::new(
+            Query::select()
+                .column((table, value_column))
+                .from(table)
+                .cond_where(
+                    Condition::all()
+                        .add(Expr::col((table, entry_column)).equals((
+                            entities::entries::Entity,
+                            entities::entries::Column::Id,
+                        )))
+                        .add(Expr::col((table, field_column)).eq(field_id)),
+                )
+                .limit(1)
+                .to_owned()
+                .into_sub_query_statement(),
+        ),
+    )
+}
@@ -0,0 +1,93 @@
+use juniper::GraphQLObject;
+use sea_orm::{
+    FromQueryResult, QueryFilter, QuerySelect, QueryTrait,
+    prelude::Expr,
+    sea_query::{Alias, JoinType, NullOrdering, Order, Query},
+};
+
+use super::ordering::SortKey;
+
+// Plain text of a rendered Typst value, with its markup tags replaced by spaces
+pub fn typst_plain_text(rendered: &str) -> String {
+    format!("regexp_replace({}, '<[^>]*>', ' ', 'g')", rendered)
+}
+
+// Searchable text of an entry: text values, text list elements and the plain
+// text of rendered Typst values
+fn search_document() -> String {
+    format!(
+        "concat_ws(' ', \
+        (SELECT string_agg(v.value, ' ') FROM entry_text_values v WHERE v.entry_id = entries.id), \
+        (SELECT string_agg(array_to_string(v.value, ' '), ' ') FROM entry_text_list_values v WHERE v.entry_id = entries.id), \
+        (SELECT string_agg({}, ' ') FROM entry_typst_text_values v WHERE v.entry_id = entries.id))",
+        typst_plain_text("v.rendered")
+    )
+}
+
+#[derive(GraphQLObject, Clone)]
+pub struct SearchMatch {
+    pub rank: f64,
+    pub headline: String, // Matching fragment with terms wrapped in <b></b>
+}
+
+#[derive(FromQueryResult)]
+pub struct SearchHit {
+    #[sea_orm(nested)]
+    pub entry: entities::entries::Model,
+    pub search_rank: f32,
+    pub search_headline: String,
+}
+
+// Rank of an entry joined by `apply_search`
+const SEARCH_RANK: &str = "ts_rank(entry_search.search_vector, entry_search.search_query)";
+
+// Best matches first, for queries restricted by `apply_search`
+pub fn search_rank_key() -> SortKey {
+    SortKey {
+        name: "search:rank".to_string(),
+        expr: Expr::cust(SEARCH_RANK),
+        order: Order::Desc,
+        nulls: NullOrdering::Last,
+        cast: "real",
+    }
+}
+
+// Restrict an entries query to full-text matches of `text`, selecting the rank
+// and headline columns read by `SearchHit`. Order it by `search_rank_key`.
+pub fn apply_search(
+    mut query: sea_orm::Select<entities::entries::Entity>,
+    text: &str,
+) -> sea_orm::Select<entities::entries::Entity> {
+    // The document and its tsvector are built once per entry in a lateral
+    // subquery, then shared by the match, the rank and the headline.
+    // OFFSET 0 keeps the planner from inlining either back into each use.
+    let document = Query::select()
+        .expr_as(Expr::cust(search_document()), Alias::new("search_document"))
+        .offset(0)
+        .to_owned();
+    let search = Query::select()
+        .column((Alias::new("document"), Alias::new("search_document")))
+        .expr_as(
+            Expr::cust("to_tsvector('english', document.search_document)"),
+            Alias::new("search_vector"),
+        )
+        .expr_as(
+            Expr::cust_with_values(
+                "websearch_to_tsquery('english', $1)",
+                [sea_orm::Value::String(Some(Box::new(text.to_string())))],
+            ),
+            Alias::new("search_query"),
+        )
+        .from_subquery(document, Alias::new("document"))
+        .offset(0)
+        .to_owned();
+    QueryTrait::query(&mut query).join_lateral(JoinType::InnerJoin, search, Alias::new("entry_search"), Expr::cust("TRUE"));
+
+    query
+        .filter(Expr::cust("entry_search.search_vector @@ entry_search.search_query"))
+        .column_as(Expr::cust(SEARCH_RANK), "search_rank")
+        .column_as(
+            Expr::cust("ts_headline('english', entry_search.search_document, entry_search.search_query)"),
+            "search_headline",
+        )
+}
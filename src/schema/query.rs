@@ -1,28 +1,72 @@
-use juniper::FieldResult;
+use juniper::{Executor, FieldResult, ScalarValue};
 
-use super::objects::collection::Collection;
+use super::objects::{
+    collection::Collection,
+    connection::{PageArgs, PageInfo, load_page},
+    entries::{Entry, entry_prefetch},
+    ordering::{SortKey, order_by_keys},
+    search::{SearchHit, apply_search, search_rank_key},
+};
 use crate::state::AppData;
-use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, prelude::Expr};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect, prelude::Expr};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Query;
 
+// A page of collections, by page number or by Relay cursor. `items` are the
+// nodes of `edges`.
 #[derive(juniper::GraphQLObject)]
 #[graphql(context = crate::state::AppData)]
 pub struct CollectionsPage {
     pub items: Vec<Collection>,
+    pub edges: Vec<CollectionEdge>,
+    pub page_info: PageInfo,
     pub num_pages: i32,
     pub num_items: i32,
-    pub index: i32,
+    pub index: i32, // Page number, 0 when paging by cursor
     pub size: i32,
 }
 
+#[derive(juniper::GraphQLObject)]
+#[graphql(context = crate::state::AppData)]
+pub struct CollectionEdge {
+    pub node: Collection,
+    pub cursor: String,
+}
+
+// Collections matching the optional name search and creation date bounds
+fn collections_query(
+    collection_name: Option<String>,
+    created_after: Option<chrono::DateTime<chrono::Utc>>,
+    created_before: Option<chrono::DateTime<chrono::Utc>>,
+) -> sea_orm::Select<entities::collections::Entity> {
+    let mut query = entities::collections::Entity::find();
+
+    if let Some(name) = collection_name {
+        query = query.filter(Expr::cust_with_values(
+            "to_tsvector('english', name) @@ to_tsquery('english', $1)",
+            vec![sea_orm::Value::String(Some(Box::new(name)))],
+        ));
+    }
+    if let Some(after) = created_after {
+        query = query.filter(entities::collections::Column::CreatedAt.gte(after));
+    }
+    if let Some(before) = created_before {
+        query = query.filter(entities::collections::Column::CreatedAt.lte(before));
+    }
+
+    query
+}
+
 #[juniper::graphql_object(context = crate::state::AppData)]
 impl Query {
     fn add(a: i32, b: i32) -> i32 {
         a + b
     }
 
+    // Collections oldest first, paged either by page and page_size or by
+    // Relay cursors with first/after and last/before
+    #[allow(clippy::too_many_arguments)]
     async fn collections(
         ctx: &AppData,
         collection_name: Option<String>,
@@ -30,49 +74,43 @@ impl Query {
         page_size: Option<i32>,
         created_after: Option<chrono::DateTime<chrono::Utc>>,
         created_before: Option<chrono::DateTime<chrono::Utc>>,
-
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
     ) -> FieldResult<CollectionsPage> {
         let db = &ctx.db;
-        let mut query = entities::collections::Entity::find();
-
-        let page_num = page.unwrap_or(1).max(1);
-        let page_size = page_size.unwrap_or(10).max(1).min(100);
+        let query = collections_query(collection_name, created_after, created_before);
 
-        if let Some(name) = collection_name {
-            query = query.filter(Expr::cust_with_values(
-                "to_tsvector('english', name) @@ to_tsquery('english', $1)",
-                vec![sea_orm::Value::String(Some(Box::new(name)))],
-            ));
-        }
-        if let Some(after) = created_after {
-            query = query.filter(entities::collections::Column::CreatedAt.gte(after));
-        }
-        if let Some(before) = created_before {
-            query = query.filter(entities::collections::Column::CreatedAt.lte(before));
-        }
+        let keys = [
+            SortKey::asc(
+                "key:createdAt",
+                Expr::col((entities::collections::Entity, entities::collections::Column::CreatedAt)).into(),
+                "timestamp",
+            ),
+            SortKey::asc(
+                "id",
+                Expr::col((entities::collections::Entity, entities::collections::Column::Id)).into(),
+                "uuid",
+            ),
+        ];
+        let args = PageArgs { page, page_size, first, after, last, before };
+        let page = load_page::<_, entities::collections::Model>(db, query, &keys, args).await?;
 
-        let page = query.paginate(db, page_size as u64);
-        let items = page.fetch_page(page_num as u64 - 1).await?;
-        let items_and_pages = page.num_items_and_pages().await?;
-        let collections = items
+        let edges: Vec<CollectionEdge> = page
+            .items
             .into_iter()
-            .map(|c| Collection {
-                id: c.id,
-                name: c.name,
-                created_at: c.created_at.and_utc(),
-                created_by: c.created_by,
-            })
+            .map(|(c, cursor)| CollectionEdge { node: Collection::from(c), cursor })
             .collect();
-
-        let collections_page = CollectionsPage {
-            items: collections,
-            num_pages: items_and_pages.number_of_pages as i32,
-            num_items: items_and_pages.number_of_items as i32,
-            index: page_num,
-            size: page_size,
-        };
-
-        Ok(collections_page)
+        Ok(CollectionsPage {
+            items: edges.iter().map(|edge| edge.node.clone()).collect(),
+            edges,
+            page_info: page.page_info,
+            num_pages: page.num_pages,
+            num_items: page.num_items,
+            index: page.index,
+            size: page.size,
+        })
     }
 
     async fn collection(ctx: &AppData, name: String) -> FieldResult<Option<Collection>> {
@@ -82,15 +120,42 @@ impl Query {
             .one(db)
             .await?;
 
-        if let Some(c) = collection {
-            Ok(Some(Collection {
-                id: c.id,
-                name: c.name,
-                created_at: c.created_at.and_utc(),
-                created_by: c.created_by,
-            }))
-        } else {
-            Ok(None)
+        Ok(collection.map(Collection::from))
+    }
+
+    // Full-text search over entries of every collection, or of the named ones
+    async fn search_entries<S: ScalarValue>(
+        ctx: &AppData,
+        executor: &Executor<'_, '_, AppData, S>,
+        query: String,
+        collection_names: Option<Vec<String>>,
+        limit: Option<i32>,
+    ) -> FieldResult<Vec<Entry>> {
+        let db = &ctx.db;
+        let prefetch = entry_prefetch(Some(executor.look_ahead()));
+        let limit = limit.unwrap_or(20).clamp(1, 100);
+
+        let mut base_query = entities::entries::Entity::find();
+        if let Some(names) = collection_names {
+            let collection_ids: Vec<uuid::Uuid> = entities::collections::Entity::find()
+                .filter(entities::collections::Column::Name.is_in(names))
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|c| c.id)
+                .collect();
+            base_query = base_query.filter(entities::entries::Column::CollectionId.is_in(collection_ids));
         }
+
+        let entries: Vec<Entry> = order_by_keys(apply_search(base_query, &query), &[search_rank_key()])
+            .limit(limit as u64)
+            .into_model::<SearchHit>()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(Entry::from)
+            .collect();
+        ctx.loaders.prefetch(db, entries.iter().map(|e| e.id).collect(), prefetch).await?;
+        Ok(entries)
     }
 }
@@ -0,0 +1,157 @@
+use chrono::{DateTime, Datelike, Duration, Months, NaiveTime, TimeZone, Utc};
+
+/// Resolve a relative time expression like `now-7d` or `startOfMonth+1M` against `now`.
+///
+/// An expression is an optional anchor (`now`, `startOfDay`, `startOfWeek`,
+/// `startOfMonth`, `startOfYear`, defaulting to `now`; `today` is an alias of
+/// `startOfDay`) followed by any number of signed offsets with a unit: `s`, `m`,
+/// `h`, `d`, `w`, `M` (months) or `y`. Weeks start on Monday and all anchors are
+/// computed in UTC.
+pub fn resolve(expression: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let expression = expression.trim();
+    let anchor_end = expression.find(['+', '-']).unwrap_or(expression.len());
+    let (anchor, mut offsets) = expression.split_at(anchor_end);
+
+    let mut time = match anchor {
+        "" | "now" => now,
+        "startOfDay" | "today" => start_of_day(now),
+        "startOfWeek" => start_of_day(now) - Duration::days(now.weekday().num_days_from_monday() as i64),
+        "startOfMonth" => start_of_day(now) - Duration::days(now.day0() as i64),
+        "startOfYear" => start_of_day(now) - Duration::days(now.ordinal0() as i64),
+        _ => {
+            return Err(format!(
+                "Invalid relative time '{}': unknown anchor '{}', expected one of now, startOfDay, startOfWeek, startOfMonth, startOfYear",
+                expression, anchor
+            ));
+        }
+    };
+
+    while !offsets.is_empty() {
+        let negative = offsets.starts_with('-');
+        let rest = &offsets[1..];
+        let digits_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let digits = &rest[..digits_end];
+        if digits.is_empty() {
+            return Err(format!(
+                "Invalid relative time '{}': expected a number after '{}'",
+                expression, &offsets[..1]
+            ));
+        }
+        let amount: u32 = digits
+            .parse()
+            .map_err(|_| format!("Invalid relative time '{}': offset is out of range", expression))?;
+        let unit = rest[digits_end..].chars().next().ok_or_else(|| {
+            format!("Invalid relative time '{}': missing unit after '{}'", expression, amount)
+        })?;
+
+        time = apply_offset(time, amount, unit, negative).ok_or_else(|| match unit {
+            's' | 'm' | 'h' | 'd' | 'w' | 'M' | 'y' => {
+                format!("Invalid relative time '{}': offset is out of range", expression)
+            }
+            _ => format!(
+                "Invalid relative time '{}': unknown unit '{}', expected one of s, m, h, d, w, M, y",
+                expression, unit
+            ),
+        })?;
+        offsets = &rest[digits_end + unit.len_utf8()..];
+        if !offsets.is_empty() && !offsets.starts_with(['+', '-']) {
+            return Err(format!(
+                "Invalid relative time '{}': unexpected '{}'",
+                expression, offsets
+            ));
+        }
+    }
+
+    Ok(time)
+}
+
+fn start_of_day(time: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&time.date_naive().and_time(NaiveTime::MIN))
+}
+
+fn apply_offset(time: DateTime<Utc>, amount: u32, unit: char, negative: bool) -> Option<DateTime<Utc>> {
+    let duration = match unit {
+        's' => Duration::seconds(amount as i64),
+        'm' => Duration::minutes(amount as i64),
+        'h' => Duration::hours(amount as i64),
+        'd' => Duration::days(amount as i64),
+        'w' => Duration::weeks(amount as i64),
+        'M' | 'y' => {
+            let months = Months::new(if unit == 'y' { amount.checked_mul(12)? } else { amount });
+            return if negative {
+                time.checked_sub_months(months)
+            } else {
+                time.checked_add_months(months)
+            };
+        }
+        _ => return None,
+    };
+
+    if negative {
+        time.checked_sub_signed(duration)
+    } else {
+        time.checked_add_signed(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    // A Friday
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 15, 10, 30, 0).unwrap()
+    }
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn anchors() {
+        assert_eq!(resolve("now", now()), Ok(now()));
+        assert_eq!(resolve("", now()), Ok(now()));
+        assert_eq!(resolve("startOfDay", now()), Ok(at(2024, 3, 15, 0, 0)));
+        assert_eq!(resolve("today", now()), Ok(at(2024, 3, 15, 0, 0)));
+        assert_eq!(resolve("startOfWeek", now()), Ok(at(2024, 3, 11, 0, 0)));
+        assert_eq!(resolve("startOfMonth", now()), Ok(at(2024, 3, 1, 0, 0)));
+        assert_eq!(resolve("startOfYear", now()), Ok(at(2024, 1, 1, 0, 0)));
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(resolve("now-7d", now()), Ok(at(2024, 3, 8, 10, 30)));
+        assert_eq!(resolve("-2h", now()), Ok(at(2024, 3, 15, 8, 30)));
+        assert_eq!(resolve(" now+1w-30m ", now()), Ok(at(2024, 3, 22, 10, 0)));
+        assert_eq!(resolve("startOfMonth+1M", now()), Ok(at(2024, 4, 1, 0, 0)));
+        assert_eq!(resolve("startOfYear-1y", now()), Ok(at(2023, 1, 1, 0, 0)));
+    }
+
+    #[test]
+    fn month_offsets_clamp_to_the_end_of_the_month() {
+        let end_of_january = at(2024, 1, 31, 12, 0);
+        assert_eq!(resolve("now+1M", end_of_january), Ok(at(2024, 2, 29, 12, 0)));
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expression in ["now-", "now-7", "now-7dx", "now-d", "now+7q", "yesterday", "now--1d"] {
+            assert!(resolve(expression, now()).is_err(), "'{}' should be rejected", expression);
+        }
+    }
+
+    #[test]
+    fn reports_unknown_units() {
+        let error = resolve("now+7q", now()).unwrap_err();
+        assert!(error.contains("unknown unit 'q'"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_out_of_range_offsets() {
+        for expression in ["now+5000000000s", "now+4000000000w", "now+400000000y", "now-3000000000d"] {
+            let error = resolve(expression, now()).unwrap_err();
+            assert!(error.contains("out of range"), "{}: {}", expression, error);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::auth::{self, Claims};
+use crate::loaders::Loaders;
 use axum::http::HeaderMap;
 use juniper::Context as JuniperContext;
 use sea_orm::DatabaseConnection;
@@ -23,16 +24,18 @@ pub fn extract_user_from_headers(headers: &HeaderMap) -> Option<Claims> {
 pub struct AppData {
     pub db: DatabaseConnection,
     pub claims: Option<Claims>,
+    pub loaders: Arc<Loaders>, // Fresh for every request
 }
 
 impl JuniperContext for AppData {}
 
 impl AppData {
     pub fn new(db: DatabaseConnection, current_user: Option<Claims>) -> Self {
-        Self { db, claims: current_user }
+        Self { db, claims: current_user, loaders: Arc::default() }
     }
 
     /// Get the current authenticated user or return an error
+    #[allow(dead_code)]
     pub fn require_auth(&self) -> juniper::FieldResult<&Claims> {
         self.claims
             .as_ref()